@@ -0,0 +1,61 @@
+//! Iteration over a synthetic tree.
+//!
+//! The number of files defaults to one million and can be set with `PREFIX_FILE_TREE_BENCH_FILES`.
+//! For comparison, the same iteration is also timed with the file type of every path looked up
+//! by a separate system call, which is how iteration worked before it used the file types from
+//! directory listings.
+
+use prefix_file_tree::{Tree, scheme::hex::Hex};
+use std::time::{Duration, Instant};
+
+const DEFAULT_FILE_COUNT: u32 = 1_000_000;
+const ROUNDS: usize = 3;
+
+fn best_of<F: FnMut() -> usize>(mut f: F) -> (Duration, usize) {
+    let mut best = Duration::MAX;
+    let mut count = 0;
+
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        count = f();
+        best = best.min(start.elapsed());
+    }
+
+    (best, count)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let file_count = std::env::var("PREFIX_FILE_TREE_BENCH_FILES")
+        .ok()
+        .map(|value| value.parse::<u32>())
+        .transpose()?
+        .unwrap_or(DEFAULT_FILE_COUNT);
+
+    let temp_dir = tempfile::tempdir()?;
+    let tree = Tree::builder(temp_dir.path())
+        .with_scheme(Hex::<4>::default())
+        .with_prefix_part_lengths([2, 2])
+        .build()?;
+
+    let start = Instant::now();
+
+    for i in 0..file_count {
+        // Spread the names over the prefix directories.
+        drop(tree.create_file(i.wrapping_mul(2_654_435_761).to_be_bytes())?);
+    }
+
+    eprintln!("Created {file_count} files in {:?}", start.elapsed());
+
+    let (elapsed, count) = best_of(|| tree.entries().flatten().count());
+    eprintln!("entries: {count} in {elapsed:?}");
+
+    let (elapsed, count) = best_of(|| {
+        tree.entries()
+            .ignore_dir_entry_file_types()
+            .flatten()
+            .count()
+    });
+    eprintln!("entries with stat per path: {count} in {elapsed:?}");
+
+    Ok(())
+}
@@ -0,0 +1,229 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counter used to distinguish staging files created by the same process.
+static STAGING_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// What to do when committing a write for a name that already has an entry.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Policy {
+    /// Leave the existing entry in place and discard the new contents.
+    #[default]
+    Refuse,
+    /// Atomically replace the existing entry with the new contents.
+    Replace,
+}
+
+/// A file that is written to a staging location inside the tree and moved into place on commit.
+///
+/// The staging file lives in the same directory as the final path, so that the move is a single
+/// rename on the same file system. If the guard is dropped without being committed, the staging
+/// file is removed and the tree is left unchanged.
+#[derive(Debug)]
+pub struct AtomicFile {
+    file: File,
+    staging_path: PathBuf,
+    path: PathBuf,
+    policy: Policy,
+    committed: bool,
+}
+
+impl AtomicFile {
+    pub(crate) fn create(path: PathBuf, policy: Policy) -> Result<Self, crate::Error> {
+        let staging_path = staging_path(&path)?;
+        let file = crate::with_parent_directories(&staging_path, |path| File::create_new(path))?;
+
+        Ok(Self {
+            file,
+            staging_path,
+            path,
+            policy,
+            committed: false,
+        })
+    }
+
+    /// The final path of the entry.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Flush the contents to disk and move the file into place.
+    ///
+    /// Returns `false` if the policy is `Policy::Refuse` and an entry already existed (in which
+    /// case the new contents are discarded).
+    pub fn commit(mut self) -> Result<bool, crate::Error> {
+        self.file.sync_all()?;
+
+        // Marking the guard as committed means that we clean up the staging file ourselves below.
+        self.committed = true;
+
+        let written = match self.policy {
+            Policy::Replace => std::fs::rename(&self.staging_path, &self.path).map(|()| true),
+            // Hard-linking fails if the destination exists, which gives us an atomic
+            // no-clobber rename in combination with removing the staging file afterwards.
+            Policy::Refuse => match std::fs::hard_link(&self.staging_path, &self.path) {
+                Ok(()) => Ok(true),
+                Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+                Err(error) => Err(error),
+            }
+            .and_then(|written| std::fs::remove_file(&self.staging_path).map(|()| written)),
+        };
+
+        match written {
+            Ok(written) => {
+                if written && let Some(parent) = self.path.parent() {
+                    sync_directory(parent)?;
+                }
+
+                Ok(written)
+            }
+            Err(error) => {
+                // Best effort: the error we report is the one from moving the file into place.
+                let _ = std::fs::remove_file(&self.staging_path);
+
+                Err(error.into())
+            }
+        }
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            // Best effort, since we can't report errors here.
+            let _ = std::fs::remove_file(&self.staging_path);
+        }
+    }
+}
+
+/// Prefix for staging file names (within the names reserved by `crate::RESERVED_PREFIX`).
+pub(crate) const STAGING_PREFIX: &str = ".prefix-file-tree-staging-";
+
+fn staging_path(path: &Path) -> Result<PathBuf, crate::Error> {
+    let parent = path
+        .parent()
+        .ok_or_else(|| crate::Error::InvalidFile(path.to_path_buf()))?;
+
+    Ok(parent.join(format!(
+        "{STAGING_PREFIX}{}-{}",
+        std::process::id(),
+        STAGING_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )))
+}
+
+#[cfg(unix)]
+fn sync_directory(path: &Path) -> std::io::Result<()> {
+    File::open(path)?.sync_all()
+}
+
+// Directories can't be opened as files on other platforms.
+#[cfg(not(unix))]
+const fn sync_directory(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Policy;
+    use crate::{Tree, scheme};
+    use std::io::{Read, Write};
+
+    fn read_entry(
+        tree: &Tree<scheme::Utf8>,
+        name: &str,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let mut contents = String::new();
+
+        Ok(match tree.open_file(name)? {
+            Some(mut file) => {
+                file.read_to_string(&mut contents)?;
+                Some(contents)
+            }
+            None => None,
+        })
+    }
+
+    #[test]
+    fn test_put_refuse_and_replace() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([2, 2])
+            .with_extension("txt")
+            .build()?;
+
+        assert!(tree.put("abcdef", b"foo", Policy::Refuse)?);
+        assert_eq!(read_entry(&tree, "abcdef")?.as_deref(), Some("foo"));
+
+        assert!(!tree.put("abcdef", b"bar", Policy::Refuse)?);
+        assert_eq!(read_entry(&tree, "abcdef")?.as_deref(), Some("foo"));
+
+        assert!(tree.put("abcdef", b"qux", Policy::Replace)?);
+        assert_eq!(read_entry(&tree, "abcdef")?.as_deref(), Some("qux"));
+
+        // No staging files are left behind.
+        assert_eq!(std::fs::read_dir(temp_dir.path().join("ab/cd"))?.count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_atomic_dropped_without_commit() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([2])
+            .build()?;
+
+        let mut file = tree.write_atomic("abcdef", Policy::Refuse)?;
+        file.write_all(b"partial")?;
+
+        assert_eq!(read_entry(&tree, "abcdef")?, None);
+
+        // Iteration ignores the in-progress staging file.
+        assert_eq!(tree.entries().count(), 0);
+
+        drop(file);
+
+        assert_eq!(read_entry(&tree, "abcdef")?, None);
+        assert_eq!(std::fs::read_dir(temp_dir.path().join("ab"))?.count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_atomic_commit() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([2])
+            .build()?;
+
+        let mut file = tree.write_atomic("abcdef", Policy::Replace)?;
+        file.write_all(b"foo")?;
+        file.write_all(b"bar")?;
+
+        assert_eq!(file.path(), tree.path("abcdef")?);
+        assert!(file.commit()?);
+
+        assert_eq!(read_entry(&tree, "abcdef")?.as_deref(), Some("foobar"));
+
+        let entries = tree.entries().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries.len(), 1);
+
+        Ok(())
+    }
+}
@@ -61,6 +61,17 @@ impl<S> TreeBuilder<S> {
         }
     }
 
+    /// Build the tree and write its manifest file (see `Tree::open`).
+    pub fn build_with_manifest(self) -> Result<crate::Tree<S>, crate::manifest::Error>
+    where
+        S: crate::scheme::Scheme,
+    {
+        let tree = self.build()?;
+        tree.write_manifest()?;
+
+        Ok(tree)
+    }
+
     /// Internal unvalidated conversion.
     fn into_tree(self) -> crate::Tree<S> {
         crate::Tree {
@@ -122,9 +133,7 @@ impl<S> TreeBuilder<S> {
 
     #[must_use]
     pub fn with_scheme<T: crate::scheme::Scheme>(self, scheme: T) -> TreeBuilder<T> {
-        let length_constraint = T::fixed_length().map_or(self.length_constraint, |fixed_length| {
-            Some(fixed_length.into())
-        });
+        let length_constraint = scheme.length_constraint().or(self.length_constraint);
 
         TreeBuilder {
             base: self.base,
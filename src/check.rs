@@ -0,0 +1,340 @@
+use crate::{Error, Tree, scheme::Scheme};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A file that is not stored at the path its file stem implies.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Misplaced {
+    pub path: PathBuf,
+    pub expected: PathBuf,
+}
+
+/// The result of a full consistency check of a tree.
+///
+/// Each file or directory is reported at most once, for the first problem found.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Report {
+    /// The number of valid entries.
+    pub entries: usize,
+    /// The number of directories below the base directory.
+    pub directories: usize,
+    /// Files that are valid except that they are in the wrong prefix directories.
+    pub misplaced_files: Vec<Misplaced>,
+    /// Prefix directories whose names have the wrong length for their level.
+    pub invalid_prefix_parts: Vec<PathBuf>,
+    /// Files whose extension does not satisfy the extension constraint.
+    pub invalid_extensions: Vec<PathBuf>,
+    /// Files whose stem does not satisfy the length constraint (or is too short to be split).
+    pub invalid_file_stem_lengths: Vec<PathBuf>,
+    /// Files whose stem cannot be decoded by the scheme.
+    pub undecodable_file_stems: Vec<(PathBuf, crate::scheme::Error)>,
+    /// Entries that are neither files nor prefix directories where they appear.
+    pub unexpected_entries: Vec<PathBuf>,
+    /// Staging files left behind by uncommitted atomic writes.
+    pub staging_files: Vec<PathBuf>,
+    /// Directories below the base directory that contain nothing.
+    pub empty_directories: Vec<PathBuf>,
+}
+
+impl Report {
+    /// The total number of problems found.
+    #[must_use]
+    pub const fn issue_count(&self) -> usize {
+        self.misplaced_files.len()
+            + self.invalid_prefix_parts.len()
+            + self.invalid_extensions.len()
+            + self.invalid_file_stem_lengths.len()
+            + self.undecodable_file_stems.len()
+            + self.unexpected_entries.len()
+            + self.staging_files.len()
+            + self.empty_directories.len()
+    }
+
+    #[must_use]
+    pub const fn is_consistent(&self) -> bool {
+        self.issue_count() == 0
+    }
+
+    fn sort(&mut self) {
+        self.misplaced_files.sort();
+        self.invalid_prefix_parts.sort();
+        self.invalid_extensions.sort();
+        self.invalid_file_stem_lengths.sort();
+        self.undecodable_file_stems.sort_by(|a, b| a.0.cmp(&b.0));
+        self.unexpected_entries.sort();
+        self.staging_files.sort();
+        self.empty_directories.sort();
+    }
+}
+
+impl<S: Scheme> Tree<S> {
+    /// Walk the entire tree and report every inconsistency, instead of failing on the first.
+    ///
+    /// Only I/O errors abort the check. Note that staging files from atomic writes that are in
+    /// progress will be reported as left behind. The paths in each list are sorted.
+    pub fn check(&self) -> Result<Report, Error> {
+        let mut report = Report::default();
+
+        if self.base.is_dir() {
+            self.check_directory(&self.base, 0, &mut report)?;
+            report.sort();
+
+            Ok(report)
+        } else {
+            Err(Error::ExpectedDirectory(self.base.clone()))
+        }
+    }
+
+    /// Find groups of entries whose paths differ only in ASCII case.
+    ///
+    /// These would collide if the tree were copied to a case-insensitive file system, which is a
+    /// problem for schemes like `encoding::Base64Url` that use both cases. Each group is sorted.
+    pub fn case_collisions(&self) -> Result<Vec<Vec<PathBuf>>, Error> {
+        if self.base.is_dir() {
+            let mut groups = HashMap::<String, Vec<PathBuf>>::new();
+
+            for result in self.entries() {
+                // Invalid entries are reported by `check`, but a subtree we can't read is an error.
+                let entry = match result {
+                    Ok(entry) => entry,
+                    Err(crate::iter::Error::Io(error)) => return Err(error.into()),
+                    Err(_) => continue,
+                };
+
+                let key = entry.path.to_string_lossy().to_ascii_lowercase();
+
+                groups.entry(key).or_default().push(entry.path);
+            }
+
+            let mut collisions = groups
+                .into_values()
+                .filter(|group| group.len() > 1)
+                .map(|mut group| {
+                    group.sort();
+                    group
+                })
+                .collect::<Vec<_>>();
+
+            collisions.sort();
+
+            Ok(collisions)
+        } else {
+            Err(Error::ExpectedDirectory(self.base.clone()))
+        }
+    }
+
+    /// Check the contents of a directory, returning whether it is empty.
+    fn check_directory(
+        &self,
+        path: &Path,
+        level: usize,
+        report: &mut Report,
+    ) -> Result<bool, Error> {
+        let mut is_empty = true;
+
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            let entry_path = entry.path();
+
+            is_empty = false;
+
+            if crate::is_reserved(&file_name) {
+                if file_name
+                    .as_encoded_bytes()
+                    .starts_with(crate::atomic::STAGING_PREFIX.as_bytes())
+                {
+                    report.staging_files.push(entry_path);
+                }
+
+                continue;
+            }
+
+            // We follow symbolic links here, for consistency with iteration.
+            match std::fs::metadata(&entry_path) {
+                Ok(metadata) if metadata.is_dir() => {
+                    match self.prefix_part_lengths.get(level) {
+                        Some(prefix_part_length) => {
+                            report.directories += 1;
+
+                            // We still look inside, since any files will be reported as misplaced.
+                            let is_entry_empty =
+                                self.check_directory(&entry_path, level + 1, report)?;
+
+                            if self.scheme.prefix_unit().length(&file_name)
+                                != Some(*prefix_part_length)
+                            {
+                                report.invalid_prefix_parts.push(entry_path);
+                            } else if is_entry_empty {
+                                report.empty_directories.push(entry_path);
+                            }
+                        }
+                        None => {
+                            report.unexpected_entries.push(entry_path);
+                        }
+                    }
+                }
+                Ok(metadata) if metadata.is_file() => {
+                    self.check_file(entry_path, report);
+                }
+                _ => {
+                    report.unexpected_entries.push(entry_path);
+                }
+            }
+        }
+
+        Ok(is_empty)
+    }
+
+    fn check_file(&self, path: PathBuf, report: &mut Report) {
+        if self.validate_extension(&path).is_err() {
+            report.invalid_extensions.push(path);
+        } else if self.validate_file_stem_length(&path).is_err() {
+            report.invalid_file_stem_lengths.push(path);
+        } else if let Some(Err(error)) = path
+            .file_stem()
+            .map(|file_stem| self.scheme.name_from_file_stem(file_stem))
+        {
+            report.undecodable_file_stems.push((path, error));
+        } else {
+            match self.expected_path(&path) {
+                Some(expected) if expected == path => {
+                    report.entries += 1;
+                }
+                Some(expected) => {
+                    report.misplaced_files.push(Misplaced { path, expected });
+                }
+                None => {
+                    report.invalid_file_stem_lengths.push(path);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Misplaced;
+    use crate::test_support::touch;
+    use crate::{Tree, atomic, scheme};
+
+    #[test]
+    fn test_check_reports_all_problems() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let base = temp_dir.path();
+        let tree = Tree::builder(base)
+            .with_scheme(scheme::hex::Hex::<3>::default())
+            .with_prefix_part_lengths([2, 2])
+            .with_extension("bin")
+            .build()?;
+
+        for name in [[0, 1, 2], [0, 1, 3], [255, 0, 0]] {
+            assert!(tree.put(name, b"", atomic::Policy::Refuse)?);
+        }
+
+        touch(base.join("00/02/000102.bin"))?;
+        touch(base.join("000/01/000104.bin"))?;
+        touch(base.join("00/01/000105.txt"))?;
+        touch(base.join("00/01/0001.bin"))?;
+        touch(base.join("00/01/00010x.bin"))?;
+        touch(base.join("00/01/.prefix-file-tree-staging-1-1"))?;
+        std::fs::create_dir_all(base.join("00/01/000106"))?;
+        std::fs::create_dir_all(base.join("ff/ff"))?;
+        std::fs::create_dir_all(base.join("ff/f"))?;
+
+        let report = tree.check()?;
+
+        assert_eq!(report.entries, 3);
+        assert_eq!(report.directories, 9);
+        assert_eq!(
+            report.misplaced_files,
+            vec![
+                Misplaced {
+                    path: base.join("00/02/000102.bin"),
+                    expected: base.join("00/01/000102.bin")
+                },
+                Misplaced {
+                    path: base.join("000/01/000104.bin"),
+                    expected: base.join("00/01/000104.bin")
+                }
+            ]
+        );
+        assert_eq!(
+            report.invalid_prefix_parts,
+            vec![base.join("000"), base.join("ff/f")]
+        );
+        assert_eq!(
+            report.invalid_extensions,
+            vec![base.join("00/01/000105.txt")]
+        );
+        assert_eq!(
+            report.invalid_file_stem_lengths,
+            vec![base.join("00/01/0001.bin")]
+        );
+        assert_eq!(
+            report.undecodable_file_stems,
+            vec![(
+                base.join("00/01/00010x.bin"),
+                scheme::Error::InvalidByte(b'x')
+            )]
+        );
+        assert_eq!(report.unexpected_entries, vec![base.join("00/01/000106")]);
+        assert_eq!(
+            report.staging_files,
+            vec![base.join("00/01/.prefix-file-tree-staging-1-1")]
+        );
+        assert_eq!(report.empty_directories, vec![base.join("ff/ff")]);
+        assert_eq!(report.issue_count(), 10);
+        assert!(!report.is_consistent());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_consistent_tree() -> Result<(), Box<dyn std::error::Error>> {
+        let tree = Tree::builder("examples/extensions/fixed-01/")
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([2, 2, 2])
+            .with_length(8)
+            .with_extension("txt")
+            .build()?;
+
+        let report = tree.check()?;
+
+        assert_eq!(report.entries, 2);
+        assert_eq!(report.directories, 6);
+        assert!(report.is_consistent());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "data-encoding")]
+    #[test]
+    fn test_case_collisions() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let base = temp_dir.path();
+        let tree = Tree::builder(base)
+            .with_scheme(scheme::encoding::Base64Url::<3>)
+            .with_prefix_part_lengths([1])
+            .build()?;
+
+        // These are "abcd", "ABCD", "aBcD", and "abce".
+        for name in [[105, 183, 29], [0, 16, 131], [104, 23, 3], [105, 183, 30]] {
+            drop(tree.create_file(name)?);
+        }
+
+        // Invalid entries don't stop the search.
+        touch(base.join("a/a!cd"))?;
+
+        assert_eq!(
+            tree.case_collisions()?,
+            vec![vec![
+                base.join("A/ABCD"),
+                base.join("a/aBcD"),
+                base.join("a/abcd")
+            ]]
+        );
+
+        Ok(())
+    }
+}
@@ -14,6 +14,17 @@ pub enum Length {
     Range(usize, usize),
 }
 
+impl Length {
+    /// Whether the given length satisfies this constraint.
+    #[must_use]
+    pub(crate) const fn contains(self, length: usize) -> bool {
+        match self {
+            Self::Fixed(fixed) => length == fixed,
+            Self::Range(minimum, maximum) => length >= minimum && length < maximum,
+        }
+    }
+}
+
 impl From<usize> for Length {
     fn from(value: usize) -> Self {
         Self::Fixed(value)
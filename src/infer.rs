@@ -0,0 +1,690 @@
+use crate::{
+    Error, Tree, constraint,
+    scheme::{Case, PrefixUnit, Scheme},
+};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// The default number of files to sample.
+const DEFAULT_SAMPLE_SIZE: usize = 1024;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Options {
+    sample_size: Option<usize>,
+    prefix_unit: PrefixUnit,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            sample_size: Some(DEFAULT_SAMPLE_SIZE),
+            prefix_unit: PrefixUnit::default(),
+        }
+    }
+}
+
+impl Options {
+    /// Examine at most this many files, spread across the tree's branches.
+    #[must_use]
+    pub const fn with_sample_size(self, sample_size: usize) -> Self {
+        Self {
+            sample_size: Some(sample_size),
+            ..self
+        }
+    }
+
+    /// Examine every file in the tree.
+    #[must_use]
+    pub const fn with_full_scan(self) -> Self {
+        Self {
+            sample_size: None,
+            ..self
+        }
+    }
+
+    /// Measure lengths in the given unit.
+    #[must_use]
+    pub const fn with_prefix_unit(self, prefix_unit: PrefixUnit) -> Self {
+        Self {
+            prefix_unit,
+            ..self
+        }
+    }
+}
+
+/// A disagreement between the sampled files and the inferred layout.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Conflict {
+    /// A file whose directories (below the base) have different name lengths than the inferred
+    /// prefix part lengths.
+    PrefixPartLengths {
+        path: PathBuf,
+        prefix_part_lengths: Vec<usize>,
+    },
+    /// A file whose name can't be measured in the prefix unit.
+    InvalidFileName(PathBuf),
+    /// Files both with and without extensions were found, so the extension is left unconstrained.
+    MixedExtensions { with: usize, without: usize },
+}
+
+/// The layout inferred from a sample of files.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Inference {
+    /// The most common sequence of directory name lengths.
+    pub prefix_part_lengths: Vec<usize>,
+    /// The file stem lengths of the files that agree with the prefix part lengths.
+    pub length_constraint: Option<constraint::Length>,
+    /// The extension constraint, or nothing if files with and without extensions were found.
+    pub extension_constraint: Option<constraint::Extension>,
+    /// The number of files examined.
+    pub sampled_files: usize,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Inference {
+    /// The proportion of the sampled files that agree with the inferred prefix part lengths.
+    #[must_use]
+    pub fn confidence(&self) -> f64 {
+        let disagreeing = self
+            .conflicts
+            .iter()
+            .filter(|conflict| !matches!(conflict, Conflict::MixedExtensions { .. }))
+            .count();
+
+        #[allow(clippy::cast_precision_loss)]
+        if self.sampled_files == 0 {
+            0.0
+        } else {
+            (self.sampled_files - disagreeing) as f64 / self.sampled_files as f64
+        }
+    }
+
+    #[must_use]
+    pub const fn is_unambiguous(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// A scheme that could have been used to name the sampled files, with its parameters.
+///
+/// The `Display` representation is the scheme's identifier (see `Scheme::identifier`).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SchemeCandidate {
+    /// `hex::Hex` with `N = length` if all stems have the same length, or otherwise
+    /// `hex::AnyLengthHex`.
+    Hex {
+        case: Case,
+        length: Option<usize>,
+    },
+    /// `encoding::Base32` with `N = length` if all stems have the same length (and no partial
+    /// blocks), or otherwise `encoding::AnyLengthBase32` without padding.
+    #[cfg(feature = "data-encoding")]
+    Base32 {
+        case: Case,
+        length: Option<usize>,
+    },
+    Utf8,
+    Identity,
+}
+
+impl std::fmt::Display for SchemeCandidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Hex {
+                case,
+                length: Some(length),
+            } => write!(f, "hex-{length}-{}", case.as_str()),
+            Self::Hex { case, length: None } => write!(f, "hex-any-{}", case.as_str()),
+            #[cfg(feature = "data-encoding")]
+            Self::Base32 {
+                case,
+                length: Some(length),
+            } => write!(f, "base32-{length}-{}", case.as_str()),
+            #[cfg(feature = "data-encoding")]
+            Self::Base32 { case, length: None } => write!(f, "base32-any-{}", case.as_str()),
+            Self::Utf8 => write!(f, "utf8"),
+            Self::Identity => write!(f, "identity"),
+        }
+    }
+}
+
+/// What we learn about a single sampled file.
+struct Observation {
+    path: PathBuf,
+    prefix_part_lengths: Vec<usize>,
+    file_stem_length: Option<usize>,
+    extension: Option<OsString>,
+}
+
+impl<S: Scheme> Tree<S> {
+    /// Open a tree with a layout inferred from a sample of its files.
+    ///
+    /// Lengths are measured in the scheme's prefix unit (overriding the options). The result will
+    /// be empty if and only if no files were found.
+    pub fn open_with_inference<P: AsRef<Path>>(
+        base: P,
+        scheme: S,
+        options: Options,
+    ) -> Result<Option<(Self, Inference)>, Error> {
+        let options = options.with_prefix_unit(scheme.prefix_unit());
+
+        Ok(Tree::infer_layout(&base, options)?.map(|inference| {
+            (
+                Self {
+                    base: base.as_ref().to_path_buf(),
+                    length_constraint: inference.length_constraint,
+                    extension_constraint: inference.extension_constraint.clone(),
+                    prefix_part_lengths: inference.prefix_part_lengths.clone(),
+                    scheme,
+                },
+                inference,
+            )
+        }))
+    }
+}
+
+impl Tree<crate::scheme::Identity> {
+    /// Infer the layout of a store by examining files from across its branches.
+    ///
+    /// Unlike `infer_prefix_part_lengths` and `infer_extension_constraint`, this doesn't rely on
+    /// the first entry in each directory being valid, and it reports any files that disagree
+    /// with the result. The result will be empty if and only if no files were found.
+    pub fn infer_layout<P: AsRef<Path>>(
+        base: P,
+        options: Options,
+    ) -> Result<Option<Inference>, Error> {
+        let base = base.as_ref();
+
+        if base.is_dir() {
+            let mut observations = vec![];
+
+            sample(
+                base,
+                &mut vec![],
+                options.sample_size.unwrap_or(usize::MAX),
+                options.prefix_unit,
+                &mut observations,
+            )?;
+
+            Ok(infer(&observations))
+        } else {
+            Err(Error::ExpectedDirectory(base.to_path_buf()))
+        }
+    }
+
+    /// Propose schemes that could have been used to name the files in a store, most specific
+    /// first.
+    ///
+    /// The file stems of a sample of files are examined (see `infer_layout`), and the result will
+    /// be empty if and only if no files were found.
+    pub fn infer_schemes<P: AsRef<Path>>(
+        base: P,
+        options: Options,
+    ) -> Result<Vec<SchemeCandidate>, Error> {
+        let base = base.as_ref();
+
+        if base.is_dir() {
+            let mut observations = vec![];
+
+            sample(
+                base,
+                &mut vec![],
+                options.sample_size.unwrap_or(usize::MAX),
+                options.prefix_unit,
+                &mut observations,
+            )?;
+
+            let file_stems = observations
+                .iter()
+                .filter_map(|observation| observation.path.file_stem())
+                .map(std::ffi::OsStr::to_str)
+                .collect::<Option<Vec<_>>>();
+
+            Ok(match file_stems {
+                _ if observations.is_empty() => vec![],
+                Some(file_stems) => infer_schemes(&file_stems),
+                None => vec![SchemeCandidate::Identity],
+            })
+        } else {
+            Err(Error::ExpectedDirectory(base.to_path_buf()))
+        }
+    }
+}
+
+fn infer_schemes(file_stems: &[&str]) -> Vec<SchemeCandidate> {
+    let mut candidates = vec![];
+
+    let lengths = file_stems
+        .iter()
+        .map(|file_stem| file_stem.len())
+        .collect::<std::collections::HashSet<_>>();
+    let uniform_length = if lengths.len() == 1 {
+        lengths.into_iter().next()
+    } else {
+        None
+    };
+
+    let chars = || file_stems.iter().flat_map(|file_stem| file_stem.chars());
+    let case = infer_case(chars());
+
+    if chars().all(|c| c.is_ascii_hexdigit())
+        && file_stems
+            .iter()
+            .all(|file_stem| file_stem.len().is_multiple_of(2))
+    {
+        candidates.push(SchemeCandidate::Hex {
+            case,
+            length: uniform_length.map(|length| length / 2),
+        });
+    }
+
+    #[cfg(feature = "data-encoding")]
+    if chars().all(|c| c.is_ascii_alphabetic() || ('2'..='7').contains(&c)) {
+        if let Some(length) = uniform_length
+            && length.is_multiple_of(8)
+        {
+            candidates.push(SchemeCandidate::Base32 {
+                case,
+                length: Some(length / 8 * 5),
+            });
+        } else if file_stems
+            .iter()
+            .all(|file_stem| [0, 2, 4, 5, 7].contains(&(file_stem.len() % 8)))
+        {
+            // These are the only possible lengths for unpadded Base32 strings.
+            candidates.push(SchemeCandidate::Base32 { case, length: None });
+        }
+    }
+
+    candidates.push(SchemeCandidate::Utf8);
+    candidates.push(SchemeCandidate::Identity);
+    candidates
+}
+
+/// Lowercase is assumed if there are no letters.
+fn infer_case<I: Iterator<Item = char>>(chars: I) -> Case {
+    let (mut lower, mut upper) = (false, false);
+
+    for c in chars {
+        lower |= c.is_lowercase();
+        upper |= c.is_uppercase();
+    }
+
+    match (lower, upper) {
+        (true, true) => Case::Any,
+        (false, true) => Case::Upper,
+        _ => Case::Lower,
+    }
+}
+
+/// Observe up to `budget` files under the given directory, returning the number observed.
+///
+/// If there are more entries than the budget allows, evenly spaced entries are chosen, and the
+/// budget is divided between subdirectories, with any unused budget passed on to later ones.
+fn sample(
+    path: &Path,
+    prefix_part_lengths: &mut Vec<usize>,
+    budget: usize,
+    prefix_unit: PrefixUnit,
+    observations: &mut Vec<Observation>,
+) -> Result<usize, Error> {
+    let mut entries = std::fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    entries.retain(|path| {
+        path.file_name()
+            .is_some_and(|file_name| !crate::is_reserved(file_name))
+    });
+    entries.sort();
+
+    if entries.len() > budget {
+        let count = entries.len();
+
+        entries = (0..budget)
+            .map(|i| std::mem::take(&mut entries[i * count / budget]))
+            .collect();
+    }
+
+    let mut used = 0;
+
+    for (i, entry_path) in entries.iter().enumerate() {
+        let remaining = budget - used;
+
+        if remaining == 0 {
+            break;
+        }
+
+        // We follow symbolic links here, for consistency with iteration.
+        let Ok(metadata) = std::fs::metadata(entry_path) else {
+            continue;
+        };
+
+        if metadata.is_file() {
+            observations.push(Observation {
+                path: entry_path.clone(),
+                prefix_part_lengths: prefix_part_lengths.clone(),
+                file_stem_length: entry_path
+                    .file_stem()
+                    .and_then(|file_stem| prefix_unit.length(file_stem)),
+                extension: entry_path.extension().map(std::ffi::OsStr::to_os_string),
+            });
+
+            used += 1;
+        } else if metadata.is_dir() {
+            let Some(length) = entry_path
+                .file_name()
+                .and_then(|directory_name| prefix_unit.length(directory_name))
+            else {
+                continue;
+            };
+
+            let share = remaining.div_ceil(entries.len() - i);
+
+            prefix_part_lengths.push(length);
+            used += sample(
+                entry_path,
+                prefix_part_lengths,
+                share,
+                prefix_unit,
+                observations,
+            )?;
+            prefix_part_lengths.pop();
+        }
+    }
+
+    Ok(used)
+}
+
+fn infer(observations: &[Observation]) -> Option<Inference> {
+    let mut counts = HashMap::<&[usize], usize>::new();
+
+    for observation in observations {
+        *counts.entry(&observation.prefix_part_lengths).or_default() += 1;
+    }
+
+    // Ties are broken by preferring fewer levels and then shorter prefix parts.
+    let prefix_part_lengths = counts
+        .into_iter()
+        .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then_with(|| b.cmp(a)))?
+        .0
+        .to_vec();
+
+    let mut conflicts = vec![];
+    let mut file_stem_lengths = None;
+    let (mut with, mut without) = (0, 0);
+    let mut extensions = HashMap::<OsString, usize>::new();
+
+    for observation in observations {
+        if observation.prefix_part_lengths != prefix_part_lengths {
+            conflicts.push(Conflict::PrefixPartLengths {
+                path: observation.path.clone(),
+                prefix_part_lengths: observation.prefix_part_lengths.clone(),
+            });
+        } else if let Some(file_stem_length) = observation.file_stem_length {
+            let (minimum, maximum) =
+                file_stem_lengths.get_or_insert((file_stem_length, file_stem_length));
+
+            *minimum = file_stem_length.min(*minimum);
+            *maximum = file_stem_length.max(*maximum);
+
+            match &observation.extension {
+                Some(extension) => {
+                    with += 1;
+                    *extensions.entry(extension.clone()).or_default() += 1;
+                }
+                None => {
+                    without += 1;
+                }
+            }
+        } else {
+            conflicts.push(Conflict::InvalidFileName(observation.path.clone()));
+        }
+    }
+
+    let length_constraint = file_stem_lengths.map(|(minimum, maximum)| {
+        if minimum == maximum {
+            constraint::Length::Fixed(minimum)
+        } else {
+            constraint::Length::Range(minimum, maximum + 1)
+        }
+    });
+
+    let extension_constraint = if with == 0 {
+        Some(constraint::Extension::None)
+    } else if without == 0 {
+        Some(if extensions.len() == 1 {
+            extensions
+                .into_keys()
+                .next()
+                .and_then(|extension| extension.into_string().ok())
+                .map_or(constraint::Extension::Any, constraint::Extension::Fixed)
+        } else {
+            constraint::Extension::Any
+        })
+    } else {
+        conflicts.push(Conflict::MixedExtensions { with, without });
+
+        None
+    };
+
+    Some(Inference {
+        prefix_part_lengths,
+        length_constraint,
+        extension_constraint,
+        sampled_files: observations.len(),
+        conflicts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Conflict, Options, SchemeCandidate};
+    use crate::{Tree, constraint, scheme, scheme::Scheme};
+
+    #[test]
+    fn test_infer_layout_with_stray_entries() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let base = temp_dir.path();
+        let tree = Tree::builder(base)
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([2, 1])
+            .with_extension("txt")
+            .build()?;
+
+        for i in 0..100 {
+            drop(tree.create_file(&format!("{}abc", i * 11))?);
+        }
+
+        // These would mislead the first-entry inference.
+        std::fs::create_dir_all(base.join("!/x"))?;
+        std::fs::write(base.join("!/x/y.txt"), b"")?;
+        std::fs::write(base.join("!!"), b"")?;
+
+        let inference =
+            Tree::infer_layout(base, Options::default().with_full_scan())?.expect("Expected files");
+
+        assert_eq!(inference.prefix_part_lengths, vec![2, 1]);
+        assert_eq!(
+            inference.length_constraint,
+            Some(constraint::Length::Range(4, 8))
+        );
+        assert_eq!(
+            inference.extension_constraint,
+            Some(constraint::Extension::Fixed("txt".to_string()))
+        );
+        assert_eq!(inference.sampled_files, 102);
+        assert_eq!(
+            inference.conflicts,
+            vec![
+                Conflict::PrefixPartLengths {
+                    path: base.join("!/x/y.txt"),
+                    prefix_part_lengths: vec![1, 1]
+                },
+                Conflict::PrefixPartLengths {
+                    path: base.join("!!"),
+                    prefix_part_lengths: vec![]
+                },
+            ]
+        );
+        assert!((inference.confidence() - 100.0 / 102.0).abs() < f64::EPSILON);
+
+        let inference =
+            Tree::infer_layout(base, Options::default().with_sample_size(10))?.expect("Files");
+
+        assert_eq!(inference.prefix_part_lengths, vec![2, 1]);
+        assert_eq!(inference.sampled_files, 10);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_infer_schemes() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::hex::Hex::<20>::default())
+            .with_prefix_part_lengths([2, 2])
+            .build()?;
+
+        for i in 0..20u8 {
+            drop(tree.create_file([i.wrapping_mul(67); 20])?);
+        }
+
+        let candidates = Tree::infer_schemes(temp_dir.path(), Options::default())?;
+
+        assert_eq!(
+            candidates,
+            vec![
+                SchemeCandidate::Hex {
+                    case: scheme::Case::Lower,
+                    length: Some(20)
+                },
+                SchemeCandidate::Utf8,
+                SchemeCandidate::Identity
+            ]
+        );
+        assert_eq!(
+            candidates[0].to_string(),
+            tree.scheme.identifier().unwrap_or_default()
+        );
+
+        assert_eq!(
+            Tree::infer_schemes("examples/extensions/fixed-01/", Options::default())?,
+            vec![
+                SchemeCandidate::Hex {
+                    case: scheme::Case::Lower,
+                    length: Some(4)
+                },
+                SchemeCandidate::Utf8,
+                SchemeCandidate::Identity
+            ]
+        );
+
+        let temp_dir = tempfile::tempdir()?;
+
+        std::fs::write(temp_dir.path().join("abc"), b"")?;
+        std::fs::write(temp_dir.path().join("Abcd"), b"")?;
+
+        assert_eq!(
+            Tree::infer_schemes(temp_dir.path(), Options::default())?,
+            vec![SchemeCandidate::Utf8, SchemeCandidate::Identity]
+        );
+
+        std::fs::remove_file(temp_dir.path().join("abc"))?;
+
+        assert_eq!(
+            Tree::infer_schemes(temp_dir.path(), Options::default())?[0],
+            SchemeCandidate::Hex {
+                case: scheme::Case::Any,
+                length: Some(2)
+            }
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "data-encoding")]
+    #[test]
+    fn test_infer_schemes_base32() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::encoding::Base32::<5>::new(scheme::Case::Upper))
+            .with_prefix_part_lengths([2])
+            .build()?;
+
+        for i in 0..20u8 {
+            drop(tree.create_file([i, 1, 2, 3, 4])?);
+        }
+
+        assert_eq!(
+            Tree::infer_schemes(temp_dir.path(), Options::default())?[0],
+            SchemeCandidate::Base32 {
+                case: scheme::Case::Upper,
+                length: Some(5)
+            }
+        );
+
+        let temp_dir = tempfile::tempdir()?;
+
+        std::fs::write(temp_dir.path().join("Abcd"), b"")?;
+        std::fs::write(temp_dir.path().join("abcdefg"), b"")?;
+
+        assert_eq!(
+            Tree::infer_schemes(temp_dir.path(), Options::default())?,
+            vec![
+                SchemeCandidate::Base32 {
+                    case: scheme::Case::Any,
+                    length: None
+                },
+                SchemeCandidate::Utf8,
+                SchemeCandidate::Identity
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_infer_layout_extensions() -> Result<(), Box<dyn std::error::Error>> {
+        let inference = Tree::infer_layout("examples/extensions/mixed-01/", Options::default())?
+            .expect("Expected files");
+
+        assert_eq!(inference.prefix_part_lengths, vec![2, 2, 2]);
+        assert_eq!(
+            inference.length_constraint,
+            Some(constraint::Length::Fixed(8))
+        );
+        assert_eq!(inference.extension_constraint, None);
+        assert_eq!(
+            inference.conflicts,
+            vec![Conflict::MixedExtensions {
+                with: 1,
+                without: 1
+            }]
+        );
+
+        let (tree, inference) = Tree::open_with_inference(
+            "examples/extensions/fixed-01/",
+            scheme::Utf8,
+            Options::default(),
+        )?
+        .expect("Expected files");
+
+        assert!(inference.is_unambiguous());
+        assert_eq!(
+            tree.extension_constraint,
+            Some(constraint::Extension::Fixed("txt".to_string()))
+        );
+        assert_eq!(tree.entries().count(), 2);
+
+        let temp_dir = tempfile::tempdir()?;
+
+        assert_eq!(
+            Tree::infer_layout(temp_dir.path(), Options::default())?,
+            None
+        );
+
+        Ok(())
+    }
+}
@@ -1,6 +1,9 @@
 use crate::{Entry, scheme::Scheme};
-use std::ffi::OsString;
+use std::cmp::Ordering;
+use std::ffi::{OsStr, OsString};
+use std::ops::{Bound, RangeBounds};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -20,88 +23,296 @@ pub enum Error {
     InvalidFileStemLength(Option<usize>),
     #[error("Scheme parse error")]
     Scheme(#[from] crate::scheme::Error),
+    #[error("Misplaced entry")]
+    MisplacedEntry { path: PathBuf, expected: PathBuf },
+}
+
+/// A file or directory that was skipped by lenient iteration.
+#[derive(Debug)]
+pub struct Skipped {
+    pub path: PathBuf,
+    pub error: Error,
+}
+
+/// The position of an iteration, which can be used to resume it later.
+///
+/// This is the name string of the last entry returned, and its string representation can be
+/// stored and parsed back with `FromStr`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Checkpoint(String);
+
+impl Checkpoint {
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Checkpoint {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.to_string()))
+    }
+}
+
+/// A bound on the names returned by iteration, split into prefix parts for each level.
+#[derive(Clone, Debug)]
+struct Key {
+    /// The name's prefix parts (truncated or empty if the name is too short).
+    parts: Vec<String>,
+    name: String,
+}
+
+impl Key {
+    /// The value to compare against at the given level (the full name for files).
+    fn at(&self, level: usize) -> &str {
+        self.parts.get(level).unwrap_or(&self.name)
+    }
+}
+
+/// What a path points to, as far as we know without another system call.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Kind {
+    File,
+    Directory,
+    Other,
+    Unknown,
+}
+
+impl Kind {
+    /// Determine the kind of a directory entry, following symbolic links.
+    ///
+    /// On most platforms the file type comes from the directory listing itself, so this only
+    /// requires a system call for symbolic links.
+    fn from_dir_entry(entry: &std::fs::DirEntry) -> std::io::Result<Self> {
+        let file_type = entry.file_type()?;
+
+        Ok(if file_type.is_symlink() {
+            Self::from_path(&entry.path())
+        } else {
+            Self::from_file_type(file_type)
+        })
+    }
+
+    fn from_path(path: &Path) -> Self {
+        std::fs::metadata(path).map_or(Self::Other, |metadata| {
+            Self::from_file_type(metadata.file_type())
+        })
+    }
+
+    fn from_file_type(file_type: std::fs::FileType) -> Self {
+        if file_type.is_file() {
+            Self::File
+        } else if file_type.is_dir() {
+            Self::Directory
+        } else {
+            Self::Other
+        }
+    }
+
+    fn resolve(self, path: &Path) -> Self {
+        if self == Self::Unknown {
+            Self::from_path(path)
+        } else {
+            self
+        }
+    }
+}
+
+/// A path to visit.
+struct Node {
+    path: PathBuf,
+    kind: Kind,
+}
+
+/// The remaining paths to visit in a directory.
+struct Frame {
+    /// The paths in reverse order.
+    paths: Vec<Node>,
+    /// Whether the directory's prefix parts are all equal to the lower bound's (in which case its
+    /// contents have to be checked against the bound).
+    lower_edge: bool,
+    /// Whether the directory's prefix parts are all equal to the upper bound's.
+    upper_edge: bool,
 }
 
 pub struct Entries<'a, S> {
-    stack: Vec<Vec<PathBuf>>,
-    level: Option<usize>,
+    /// The directories being visited.
+    ///
+    /// The first frame contains only the base directory, the next frames contain prefix
+    /// directories (one frame per prefix part), and the last frame contains files.
+    stack: Vec<Frame>,
     tree: &'a crate::Tree<S>,
+    placement_validation: bool,
+    skipped: Option<Vec<Skipped>>,
+    lower: Bound<Key>,
+    upper: Bound<Key>,
+    prefix: Option<Key>,
+    descending: bool,
+    /// An exclusive bound in the direction of iteration that overrides the range's bound.
+    resume: Option<Key>,
+    checkpoint: Option<Checkpoint>,
+    /// Whether to use the file types from directory listings (see `ignore_dir_entry_file_types`).
+    dir_entry_file_types: bool,
 }
 
 impl<'a, S> Entries<'a, S> {
     pub(crate) fn new(tree: &'a crate::Tree<S>) -> Self {
         Self {
-            stack: vec![vec![tree.base.clone()]],
-            level: None,
+            stack: vec![Frame {
+                paths: vec![Node {
+                    path: tree.base.clone(),
+                    kind: Kind::Unknown,
+                }],
+                lower_edge: true,
+                upper_edge: true,
+            }],
             tree,
+            placement_validation: false,
+            skipped: None,
+            lower: Bound::Unbounded,
+            upper: Bound::Unbounded,
+            prefix: None,
+            descending: false,
+            resume: None,
+            checkpoint: None,
+            dir_entry_file_types: true,
         }
     }
 
-    fn is_last(&self) -> bool {
-        self.level == Some(self.tree.prefix_part_lengths.len())
+    /// Look up the file type of every path with a separate system call, instead of using the file
+    /// types from directory listings.
+    ///
+    /// This is how iteration used to work, and it is only useful for benchmarking.
+    #[doc(hidden)]
+    #[must_use]
+    pub fn ignore_dir_entry_file_types(self) -> Self {
+        Self {
+            dir_entry_file_types: false,
+            ..self
+        }
     }
 
-    fn current_prefix_part_length(&self) -> Option<usize> {
-        self.level
-            .and_then(|level| self.tree.prefix_part_lengths.get(level))
-            .copied()
+    /// Also check that each file is in the prefix directories that its file stem implies.
+    ///
+    /// Files that fail this check are reported as `Error::MisplacedEntry`, since they could never
+    /// be found by `Tree::open_file`.
+    #[must_use]
+    pub fn validate_placement(self) -> Self {
+        Self {
+            placement_validation: true,
+            ..self
+        }
     }
 
-    fn increment_level(&mut self) {
-        self.level = Some(self.level.take().map_or(0, |level| level + 1));
+    /// Return entries in descending order (according to the scheme's ordering).
+    ///
+    /// This can be combined with ranges and prefixes.
+    #[must_use]
+    pub fn reverse(self) -> Self {
+        Self {
+            descending: true,
+            ..self
+        }
     }
 
-    const fn decrement_level(&mut self) {
-        if let Some(level) = self.level.take()
-            && level != 0
-        {
-            self.level = Some(level - 1);
+    /// Skip invalid files and directories instead of returning errors for them.
+    ///
+    /// The skipped paths and the reasons they were skipped are available from `skipped`, and the
+    /// valid entries are returned in the usual order.
+    #[must_use]
+    pub fn lenient(self) -> Self {
+        Self {
+            skipped: Some(vec![]),
+            ..self
         }
     }
 
-    fn validate_extension<P: AsRef<Path>>(&self, path: P) -> Result<(), Option<OsString>> {
-        match &self.tree.extension_constraint {
-            None => Ok(()),
-            Some(crate::constraint::Extension::None) => path
-                .as_ref()
-                .extension()
-                .map_or(Ok(()), |extension| Err(Some(extension.to_os_string()))),
-            Some(crate::constraint::Extension::Any) => {
-                path.as_ref().extension().map_or(Err(None), |_| Ok(()))
-            }
-            Some(crate::constraint::Extension::Fixed(expected_extension)) => {
-                path.as_ref().extension().map_or(Err(None), |extension| {
-                    if **expected_extension == *extension {
-                        Ok(())
-                    } else {
-                        Err(Some(extension.to_os_string()))
-                    }
-                })
+    /// The position after the last entry returned (or the checkpoint resumed from, if none have been).
+    ///
+    /// Errors and skipped files do not change the checkpoint.
+    #[must_use]
+    pub const fn checkpoint(&self) -> Option<&Checkpoint> {
+        self.checkpoint.as_ref()
+    }
+
+    /// Also return the metadata of each entry's file (following symbolic links).
+    ///
+    /// If the metadata can't be read, the entry is treated as invalid.
+    #[must_use]
+    pub const fn with_metadata(self) -> WithMetadata<'a, S> {
+        WithMetadata { entries: self }
+    }
+
+    /// The files and directories that have been skipped so far (always empty unless lenient).
+    #[must_use]
+    pub fn skipped(&self) -> &[Skipped] {
+        self.skipped.as_deref().unwrap_or_default()
+    }
+
+    /// Return the error to the caller, or record it and return nothing if we're lenient.
+    fn fail(&mut self, path: PathBuf, error: Error) -> Option<Error> {
+        match &mut self.skipped {
+            Some(skipped) => {
+                skipped.push(Skipped { path, error });
+
+                None
             }
+            None => Some(error),
         }
     }
+}
 
-    fn validate_file_stem_length<P: AsRef<Path>>(&self, path: P) -> Result<(), Option<usize>> {
-        match &self.tree.length_constraint {
-            None => Ok(()),
-            Some(crate::constraint::Length::Fixed(length)) => {
-                path.as_ref().file_stem().map_or(Err(None), |file_stem| {
-                    if file_stem.len() == *length {
-                        Ok(())
-                    } else {
-                        Err(Some(file_stem.len()))
-                    }
-                })
-            }
-            Some(crate::constraint::Length::Range(minimum, maximum)) => {
-                path.as_ref().file_stem().map_or(Err(None), |file_stem| {
-                    if file_stem.len() >= *minimum && file_stem.len() < *maximum {
-                        Ok(())
-                    } else {
-                        Err(Some(file_stem.len()))
-                    }
+#[cfg(feature = "rayon")]
+impl<S: Scheme> Entries<'_, S> {
+    /// Split the iteration into one iterator for each top-level prefix directory, in order.
+    ///
+    /// If there are no prefix parts, the result contains only this iterator.
+    pub(crate) fn into_subtrees(self) -> Result<Vec<Self>, Error> {
+        if self.tree.prefix_part_lengths.is_empty() {
+            Ok(vec![self])
+        } else {
+            let mut paths =
+                self.path_to_paths(&self.tree.base, Kind::Unknown, None, 0, true, true)?;
+
+            // The paths are in reverse order, since they're normally visited from the end.
+            paths.reverse();
+
+            Ok(paths
+                .into_iter()
+                .map(|path| Self {
+                    // The base directory has already been visited.
+                    stack: vec![
+                        Frame {
+                            paths: vec![],
+                            lower_edge: true,
+                            upper_edge: true,
+                        },
+                        Frame {
+                            paths: vec![path],
+                            lower_edge: true,
+                            upper_edge: true,
+                        },
+                    ],
+                    tree: self.tree,
+                    placement_validation: self.placement_validation,
+                    skipped: self.skipped.as_ref().map(|_| vec![]),
+                    lower: self.lower.clone(),
+                    upper: self.upper.clone(),
+                    prefix: self.prefix.clone(),
+                    descending: self.descending,
+                    resume: self.resume.clone(),
+                    checkpoint: self.checkpoint.clone(),
+                    dir_entry_file_types: self.dir_entry_file_types,
                 })
-            }
+                .collect())
         }
     }
 }
@@ -110,74 +321,385 @@ impl<S: Scheme> Iterator for Entries<'_, S> {
     type Item = Result<Entry<S::Name>, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.stack.pop().and_then(|mut next_paths| {
-            if let Some(next_path) = next_paths.pop() {
-                if self.is_last() {
-                    self.stack.push(next_paths);
+        loop {
+            let depth = self.stack.len();
+            let frame = self.stack.last_mut()?;
+            let (lower_edge, upper_edge) = (frame.lower_edge, frame.upper_edge);
 
-                    Some(self.path_to_entry(next_path))
-                } else {
-                    self.increment_level();
+            match frame.paths.pop() {
+                Some(next) if depth > self.tree.prefix_part_lengths.len() + 1 => {
+                    match self.path_to_entry(&next.path, next.kind) {
+                        Ok(entry) => {
+                            // The file stem has already been validated as a name by the scheme.
+                            if let Some(file_stem) = entry.path.file_stem() {
+                                self.checkpoint =
+                                    Some(Checkpoint(file_stem.to_string_lossy().into_owned()));
+                            }
 
-                    self.path_to_paths(next_path, self.current_prefix_part_length())
-                        .map_or_else(
-                            |error| Some(Err(error)),
-                            |next_level| {
-                                self.stack.push(next_paths);
-                                self.stack.push(next_level);
-
-                                self.next()
-                            },
-                        )
+                            return Some(Ok(entry));
+                        }
+                        Err(error) => {
+                            if let Some(error) = self.fail(next.path, error) {
+                                return Some(Err(error));
+                            }
+                        }
+                    }
                 }
-            } else {
-                self.decrement_level();
+                Some(next) => {
+                    // The base directory is at depth one, and its prefix part length is not checked.
+                    let level = depth.checked_sub(2);
+                    let prefix_part_length = level
+                        .and_then(|level| self.tree.prefix_part_lengths.get(level))
+                        .copied();
 
-                self.next()
+                    let (lower_edge, upper_edge) = level.map_or((true, true), |level| {
+                        self.edges(&next.path, level, lower_edge, upper_edge)
+                    });
+
+                    match self.path_to_paths(
+                        &next.path,
+                        next.kind,
+                        prefix_part_length,
+                        depth - 1,
+                        lower_edge,
+                        upper_edge,
+                    ) {
+                        Ok(paths) => {
+                            self.stack.push(Frame {
+                                paths,
+                                lower_edge,
+                                upper_edge,
+                            });
+                        }
+                        Err(error) => {
+                            if let Some(error) = self.fail(next.path, error) {
+                                return Some(Err(error));
+                            }
+                        }
+                    }
+                }
+                None => {
+                    self.stack.pop();
+                }
             }
-        })
+        }
     }
 }
 
 impl<S: Scheme> Entries<'_, S> {
-    fn path_to_entry(&self, path: PathBuf) -> Result<Entry<S::Name>, Error> {
-        if path.is_file() {
-            self.validate_extension(&path)
+    /// Only return entries with names in the given range, according to the scheme's ordering.
+    ///
+    /// Prefix directories that are entirely outside of the range are never read.
+    #[must_use]
+    pub fn range<'n, R: RangeBounds<S::NameRef<'n>>>(self, range: R) -> Self
+    where
+        S::NameRef<'n>: Clone,
+    {
+        let lower = range
+            .start_bound()
+            .map(|name| self.tree.scheme.name_to_string(name.clone()).into_owned());
+        let upper = range
+            .end_bound()
+            .map(|name| self.tree.scheme.name_to_string(name.clone()).into_owned());
+
+        self.with_bounds(lower, upper)
+    }
+
+    /// Only return entries with name strings within the given bounds.
+    pub(crate) fn with_bounds(self, lower: Bound<String>, upper: Bound<String>) -> Self {
+        Self {
+            lower: lower.map(|name| self.key(name)),
+            upper: upper.map(|name| self.key(name)),
+            ..self
+        }
+    }
+
+    /// Only return entries after the given checkpoint, in the direction of iteration.
+    ///
+    /// The checkpoint should come from an iteration with the same range and prefix, since it
+    /// replaces the range's bound on that side. Entries added before the checkpoint since it was
+    /// taken are not returned.
+    #[must_use]
+    pub fn resume_after(self, checkpoint: &Checkpoint) -> Self {
+        Self {
+            resume: Some(self.key(checkpoint.0.clone())),
+            checkpoint: Some(checkpoint.clone()),
+            ..self
+        }
+    }
+
+    fn lower(&self) -> Bound<&Key> {
+        match &self.resume {
+            Some(key) if !self.descending => Bound::Excluded(key),
+            _ => self.lower.as_ref(),
+        }
+    }
+
+    fn upper(&self) -> Bound<&Key> {
+        match &self.resume {
+            Some(key) if self.descending => Bound::Excluded(key),
+            _ => self.upper.as_ref(),
+        }
+    }
+
+    /// Only return entries with name strings that start with the given prefix.
+    ///
+    /// The prefix is compared using the scheme's ordering, so for example it matches names in
+    /// either case if the scheme accepts either case.
+    ///
+    /// Prefix directories that are completely determined by the prefix are visited directly,
+    /// without listing their parent directories.
+    #[must_use]
+    pub fn with_prefix(self, prefix: &str) -> Self {
+        Self {
+            prefix: Some(self.key(prefix.to_string())),
+            ..self
+        }
+    }
+
+    fn key(&self, name: String) -> Key {
+        let prefix_unit = self.tree.scheme.prefix_unit();
+        let mut remaining = name.as_str();
+
+        let parts = self
+            .tree
+            .prefix_part_lengths
+            .iter()
+            .map(|prefix_part_length| {
+                let (part, rest) = prefix_unit
+                    .split_at(remaining, *prefix_part_length)
+                    .unwrap_or((remaining, ""));
+
+                remaining = rest;
+
+                part.to_string()
+            })
+            .collect();
+
+        Key { parts, name }
+    }
+
+    /// Compare a prefix part or file stem with a key, falling back to the byte ordering if the
+    /// scheme can't compare them (the value should be caught by later validation).
+    fn cmp_key(&self, value: &OsStr, key: &str) -> Ordering {
+        let key = OsStr::new(key);
+
+        self.tree
+            .scheme
+            .cmp_prefix_part(value, key)
+            .unwrap_or_else(|_| value.cmp(key))
+    }
+
+    /// Determine whether a prefix directory at the given level is on the edges of the bounds.
+    fn edges(&self, path: &Path, level: usize, lower_edge: bool, upper_edge: bool) -> (bool, bool) {
+        let is_edge = |edge: bool, bound: Bound<&Key>| {
+            edge && match bound {
+                Bound::Included(key) | Bound::Excluded(key) => {
+                    path.file_name().is_some_and(|directory_name| {
+                        self.cmp_key(directory_name, key.at(level)) == Ordering::Equal
+                    })
+                }
+                Bound::Unbounded => false,
+            }
+        };
+
+        (
+            is_edge(lower_edge, self.lower()),
+            is_edge(upper_edge, self.upper()),
+        )
+    }
+
+    /// Check whether a prefix directory or file (if the level is past the last prefix part) in a
+    /// directory with the given edges could contain or be an entry within the bounds.
+    fn is_in_bounds(&self, path: &Path, level: usize, lower_edge: bool, upper_edge: bool) -> bool {
+        let is_file = level == self.tree.prefix_part_lengths.len();
+        let value = if is_file {
+            path.file_stem()
+        } else {
+            path.file_name()
+        };
+
+        value.is_none_or(|value| {
+            let is_above_lower = !lower_edge
+                || match self.lower() {
+                    Bound::Included(key) => self.cmp_key(value, key.at(level)).is_ge(),
+                    Bound::Excluded(key) => {
+                        let ordering = self.cmp_key(value, key.at(level));
+
+                        if is_file {
+                            ordering.is_gt()
+                        } else {
+                            ordering.is_ge()
+                        }
+                    }
+                    Bound::Unbounded => true,
+                };
+
+            let is_below_upper = !upper_edge
+                || match self.upper() {
+                    Bound::Included(key) => self.cmp_key(value, key.at(level)).is_le(),
+                    Bound::Excluded(key) => {
+                        let ordering = self.cmp_key(value, key.at(level));
+
+                        if is_file {
+                            ordering.is_lt()
+                        } else {
+                            ordering.is_le()
+                        }
+                    }
+                    Bound::Unbounded => true,
+                };
+
+            let has_prefix = self
+                .prefix
+                .as_ref()
+                .is_none_or(|prefix| self.starts_with_key(value, prefix.at(level)));
+
+            is_above_lower && is_below_upper && has_prefix
+        })
+    }
+
+    /// Check whether a prefix part or file stem starts with a key, according to the scheme's
+    /// ordering (so that case is normalised in the same way as for ranges).
+    fn starts_with_key(&self, value: &OsStr, key: &str) -> bool {
+        value
+            .to_str()
+            .and_then(|value| value.get(..key.len()))
+            .map_or_else(
+                || value.as_encoded_bytes().starts_with(key.as_bytes()),
+                |head| self.cmp_key(OsStr::new(head), key) == Ordering::Equal,
+            )
+    }
+
+    /// Return the prefix part at the given level if it is fully determined by the prefix.
+    fn full_prefix_part(&self, level: usize) -> Option<&str> {
+        let prefix_part = self.prefix.as_ref()?.parts.get(level)?;
+
+        (self
+            .tree
+            .scheme
+            .prefix_unit()
+            .length(OsStr::new(prefix_part))
+            == self.tree.prefix_part_lengths.get(level).copied())
+        .then_some(prefix_part.as_str())
+    }
+
+    fn validate_placement_of(&self, path: &Path) -> Result<(), Error> {
+        let expected = self
+            .tree
+            .expected_path(path)
+            .ok_or_else(|| Error::InvalidFileStem(path.to_path_buf()))?;
+
+        if expected == path {
+            Ok(())
+        } else {
+            Err(Error::MisplacedEntry {
+                path: path.to_path_buf(),
+                expected,
+            })
+        }
+    }
+
+    fn path_to_entry(&self, path: &Path, kind: Kind) -> Result<Entry<S::Name>, Error> {
+        if kind.resolve(path) == Kind::File {
+            self.tree
+                .validate_extension(path)
                 .map_err(Error::InvalidExtension)?;
 
-            self.validate_file_stem_length(&path)
+            self.tree
+                .validate_file_stem_length(path)
                 .map_err(Error::InvalidFileStemLength)?;
 
             let file_stem = path
                 .file_stem()
-                .ok_or_else(|| Error::InvalidFileStem(path.clone()))?;
+                .ok_or_else(|| Error::InvalidFileStem(path.to_path_buf()))?;
 
             let name = self.tree.scheme.name_from_file_stem(file_stem)?;
 
-            Ok(Entry { name, path })
+            if self.placement_validation {
+                self.validate_placement_of(path)?;
+            }
+
+            Ok(Entry {
+                name,
+                path: path.to_path_buf(),
+            })
         } else {
-            Err(Error::ExpectedFile(path))
+            Err(Error::ExpectedFile(path.to_path_buf()))
         }
     }
+
+    /// List the contents of a directory, where `level` is the level of the contents.
     fn path_to_paths(
         &self,
-        path: PathBuf,
+        path: &Path,
+        kind: Kind,
         prefix_part_length: Option<usize>,
-    ) -> Result<Vec<PathBuf>, Error> {
-        if path.is_dir() {
-            let mut paths = std::fs::read_dir(path)?
-                .map(|entry| entry.map(|entry| entry.path()))
-                .collect::<Result<Vec<PathBuf>, std::io::Error>>()
-                .map_err(Error::from)?;
+        level: usize,
+        lower_edge: bool,
+        upper_edge: bool,
+    ) -> Result<Vec<Node>, Error> {
+        if let Some(prefix_part_length) = prefix_part_length
+            && path.file_name().is_none_or(|directory_name| {
+                self.tree.scheme.prefix_unit().length(directory_name) != Some(prefix_part_length)
+            })
+        {
+            Err(Error::InvalidPrefixPart(path.to_path_buf()))
+        } else if let Some(next_path) = self
+            .full_prefix_part(level)
+            .filter(|prefix_part| crate::scheme::validate_path_component(prefix_part).is_ok())
+            .map(|prefix_part| path.join(prefix_part))
+            .filter(|next_path| next_path.is_dir())
+        {
+            // We don't need to list the directory, since there's at most one valid option (if the
+            // directory doesn't exist, it may still be stored with a different case).
+            Ok(
+                if self.is_in_bounds(&next_path, level, lower_edge, upper_edge) {
+                    vec![Node {
+                        path: next_path,
+                        kind: Kind::Directory,
+                    }]
+                } else {
+                    vec![]
+                },
+            )
+        } else if kind.resolve(path) == Kind::Directory {
+            let mut paths = vec![];
+
+            for entry in std::fs::read_dir(path)? {
+                let entry = entry?;
+
+                if !crate::is_reserved(&entry.file_name()) {
+                    let path = entry.path();
+
+                    if self.is_in_bounds(&path, level, lower_edge, upper_edge) {
+                        paths.push(Node {
+                            kind: if self.dir_entry_file_types {
+                                Kind::from_dir_entry(&entry)?
+                            } else {
+                                Kind::Unknown
+                            },
+                            path,
+                        });
+                    }
+                }
+            }
+
+            // Files are ordered by file stem, so that extensions don't affect the order.
+            let sort_key = if level == self.tree.prefix_part_lengths.len() {
+                Path::file_stem
+            } else {
+                Path::file_name
+            };
 
             // If our ordering for prefix parts fails, we simply leave them in the original order.
             //
             // The error should be caught by later validation.
             paths.sort_by(|a, b| {
-                let directory_name_a = a.file_name();
-                let directory_name_b = b.file_name();
+                let directory_name_a = sort_key(&a.path);
+                let directory_name_b = sort_key(&b.path);
 
-                directory_name_a
+                let ordering = directory_name_a
                     .zip(directory_name_b)
                     .and_then(|(directory_name_a, directory_name_b)| {
                         self.tree
@@ -185,28 +707,671 @@ impl<S: Scheme> Entries<'_, S> {
                             .cmp_prefix_part(directory_name_a, directory_name_b)
                             .ok()
                     })
-                    .unwrap_or(std::cmp::Ordering::Equal)
-                    .reverse()
+                    .unwrap_or(std::cmp::Ordering::Equal);
+
+                // The paths are visited from the end.
+                if self.descending {
+                    ordering
+                } else {
+                    ordering.reverse()
+                }
             });
 
-            match prefix_part_length {
-                Some(prefix_part_length) => {
-                    let invalid_path = paths.iter().find(|path| {
-                        path.file_name()
-                            .is_none_or(|directory_name| directory_name.len() != prefix_part_length)
-                    });
+            Ok(paths)
+        } else {
+            Err(Error::ExpectedDirectory(path.to_path_buf()))
+        }
+    }
+}
+
+/// Entries together with the metadata of their files.
+pub struct WithMetadata<'a, S> {
+    entries: Entries<'a, S>,
+}
+
+impl<S> WithMetadata<'_, S> {
+    /// See `Entries::checkpoint`.
+    #[must_use]
+    pub const fn checkpoint(&self) -> Option<&Checkpoint> {
+        self.entries.checkpoint()
+    }
+
+    /// See `Entries::skipped`.
+    #[must_use]
+    pub fn skipped(&self) -> &[Skipped] {
+        self.entries.skipped()
+    }
+}
+
+impl<S: Scheme> Iterator for WithMetadata<'_, S> {
+    type Item = Result<(Entry<S::Name>, std::fs::Metadata), Error>;
 
-                    // Clippy is wrong here, since `map_or` would require us to clone `paths`.
-                    #[allow(clippy::option_if_let_else)]
-                    match invalid_path {
-                        Some(invalid_path) => Err(Error::InvalidPrefixPart(invalid_path.clone())),
-                        None => Ok(paths),
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.entries.next()? {
+                Ok(entry) => match std::fs::metadata(&entry.path) {
+                    Ok(metadata) => {
+                        return Some(Ok((entry, metadata)));
+                    }
+                    Err(error) => {
+                        if let Some(error) = self.entries.fail(entry.path, error.into()) {
+                            return Some(Err(error));
+                        }
                     }
+                },
+                Err(error) => {
+                    return Some(Err(error));
                 }
-                None => Ok(paths),
             }
-        } else {
-            Err(Error::ExpectedDirectory(path))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+    use crate::{Tree, scheme};
+    use std::fs::File;
+    use std::io::Write;
+    use std::ops::Bound;
+
+    #[test]
+    fn test_validate_placement() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([2, 2])
+            .with_extension("txt")
+            .build()?;
+
+        drop(tree.create_file("abcdef")?.expect("Failed to create file"));
+
+        let entries = tree
+            .entries()
+            .validate_placement()
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries.len(), 1);
+
+        // A file with the right length but in the wrong shard.
+        std::fs::create_dir_all(temp_dir.path().join("ab/xy"))?;
+        std::fs::File::create(temp_dir.path().join("ab/xy/abcdzz.txt"))?;
+
+        // Without placement validation the file is returned.
+        let entries = tree.entries().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries.len(), 2);
+
+        match tree
+            .entries()
+            .validate_placement()
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Err(Error::MisplacedEntry { path, expected }) => {
+                assert_eq!(path, temp_dir.path().join("ab/xy/abcdzz.txt"));
+                assert_eq!(expected, temp_dir.path().join("ab/cd/abcdzz.txt"));
+            }
+            other => panic!("Expected `Err(MisplacedEntry)`, got {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lenient() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let base = temp_dir.path();
+        let tree = Tree::builder(base)
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([2])
+            .with_extension("txt")
+            .build()?;
+
+        for name in ["aaaa", "abcd", "bbbb", "cccc"] {
+            drop(tree.create_file(name)?.expect("Failed to create file"));
+        }
+
+        File::create(base.join("ab/abce.bin"))?;
+        std::fs::create_dir(base.join("ab/abcf.txt"))?;
+        std::fs::create_dir(base.join("bbb"))?;
+        File::create(base.join("bbb/bbbb.txt"))?;
+        File::create(base.join("cc/..txt"))?;
+
+        // Without leniency we get errors, but iteration continues consistently afterwards.
+        let results = tree.entries().collect::<Vec<_>>();
+        assert_eq!(results.len(), 8);
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 4);
+
+        let mut entries = tree.entries().lenient();
+        let names = entries
+            .by_ref()
+            .map(|result| result.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(names, vec!["aaaa", "abcd", "bbbb", "cccc"]);
+
+        let mut skipped = entries
+            .skipped()
+            .iter()
+            .map(|skipped| (skipped.path.clone(), format!("{:?}", skipped.error)))
+            .collect::<Vec<_>>();
+        skipped.sort();
+
+        assert_eq!(
+            skipped,
+            vec![
+                (
+                    base.join("ab/abce.bin"),
+                    "InvalidExtension(Some(\"bin\"))".to_string()
+                ),
+                (
+                    base.join("ab/abcf.txt"),
+                    format!("ExpectedFile({:?})", base.join("ab/abcf.txt"))
+                ),
+                (
+                    base.join("bbb"),
+                    format!("InvalidPrefixPart({:?})", base.join("bbb"))
+                ),
+                (
+                    base.join("cc/..txt"),
+                    format!("Scheme({:?})", scheme::Error::InvalidPathComponent)
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "data-encoding")]
+    #[test]
+    fn test_range_base32() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::encoding::Base32::<5>::new(scheme::Case::Upper))
+            .with_prefix_part_lengths([1, 2])
+            .build()?;
+
+        let names = (0..200u8)
+            .map(|i| {
+                [
+                    i.wrapping_mul(37),
+                    i.wrapping_mul(11),
+                    i % 3,
+                    i.wrapping_mul(101),
+                    i,
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        for name in &names {
+            drop(tree.create_file(*name)?.expect("Failed to create file"));
+        }
+
+        let mut sorted_names = names.clone();
+        sorted_names.sort_unstable();
+
+        let all = tree
+            .entries()
+            .map(|entry| entry.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(all, sorted_names);
+
+        let bounds = [
+            names[0],
+            names[17],
+            names[99],
+            names[150],
+            [0; 5],
+            [255; 5],
+            [128, 0, 0, 0, 0],
+            [names[42][0], 0, 0, 0, 0],
+            [names[42][0], names[42][1], 255, 255, 255],
+        ];
+
+        for start in bounds {
+            let expected = sorted_names
+                .iter()
+                .filter(|name| **name >= start)
+                .copied()
+                .collect::<Vec<_>>();
+            let names = tree
+                .entries_from(start)
+                .map(|entry| entry.map(|entry| entry.name))
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(names, expected);
+
+            for end in bounds {
+                let expected = sorted_names
+                    .iter()
+                    .filter(|name| (start..end).contains(name))
+                    .copied()
+                    .collect::<Vec<_>>();
+                let names = tree
+                    .entries_range(start..end)
+                    .map(|entry| entry.map(|entry| entry.name))
+                    .collect::<Result<Vec<_>, _>>()?;
+                assert_eq!(names, expected);
+
+                let expected = sorted_names
+                    .iter()
+                    .filter(|name| (start..=end).contains(name))
+                    .copied()
+                    .collect::<Vec<_>>();
+                let names = tree
+                    .entries_range(start..=end)
+                    .map(|entry| entry.map(|entry| entry.name))
+                    .collect::<Result<Vec<_>, _>>()?;
+                assert_eq!(names, expected);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_range_with_short_bounds() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([1, 2])
+            .with_extension("txt")
+            .build()?;
+
+        let mut names = vec![
+            "abcd", "abce", "abd", "acdef", "bcd", "bbbbbbb", "b12", "abc",
+        ];
+
+        for name in &names {
+            drop(tree.create_file(name)?.expect("Failed to create file"));
+        }
+
+        names.sort_unstable();
+
+        let bounds = ["", "a", "ab", "abc", "abcd", "abcde", "abd", "b", "b2", "c"];
+
+        for start in bounds {
+            for end in bounds {
+                let expected = names
+                    .iter()
+                    .filter(|name| (start..end).contains(*name))
+                    .copied()
+                    .collect::<Vec<_>>();
+                let result = tree
+                    .entries_range(start..end)
+                    .map(|entry| entry.map(|entry| entry.name))
+                    .collect::<Result<Vec<_>, _>>()?;
+                assert_eq!(result, expected, "{start}..{end}");
+
+                let expected = names
+                    .iter()
+                    .filter(|name| (..=end).contains(*name) && **name > start)
+                    .copied()
+                    .collect::<Vec<_>>();
+                let result = tree
+                    .entries()
+                    .range((Bound::Excluded(start), Bound::Included(end)))
+                    .map(|entry| entry.map(|entry| entry.name))
+                    .collect::<Result<Vec<_>, _>>()?;
+                assert_eq!(result, expected, "({start}, {end}]");
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_prefix() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([1, 2])
+            .with_extension("txt")
+            .build()?;
+
+        let mut names = vec![
+            "abcd", "abce", "abd", "acdef", "bcd", "bbbbbbb", "b12", "abc",
+        ];
+
+        for name in &names {
+            drop(tree.create_file(name)?.expect("Failed to create file"));
+        }
+
+        names.sort_unstable();
+
+        for prefix in [
+            "", "a", "ab", "abc", "abcd", "abcde", "abd", "b", "b1", "c", "..", "a/",
+        ] {
+            let expected = names
+                .iter()
+                .filter(|name| name.starts_with(prefix))
+                .copied()
+                .collect::<Vec<_>>();
+            let result = tree
+                .entries_with_prefix(prefix)
+                .map(|entry| entry.map(|entry| entry.name))
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(result, expected, "{prefix}");
+        }
+
+        // Prefixes compose with ranges.
+        let result = tree
+            .entries_with_prefix("ab")
+            .range("abcd"..)
+            .map(|entry| entry.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(result, vec!["abcd", "abce", "abd"]);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "data-encoding")]
+    #[test]
+    fn test_with_prefix_base32() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::encoding::Base32::<20>::new(scheme::Case::Upper))
+            .with_prefix_part_lengths([3, 2])
+            .build()?;
+
+        drop(tree.create_file(*b"abcd_abcd_abcd_abcd_")?);
+        drop(tree.create_file(*b"abcd_abcd_abcd_efgh_")?);
+        drop(tree.create_file([255; 20])?);
+
+        let names = tree
+            .entries_with_prefix("MFRGG")
+            .map(|entry| entry.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            names,
+            vec![*b"abcd_abcd_abcd_abcd_", *b"abcd_abcd_abcd_efgh_"]
+        );
+
+        assert_eq!(
+            tree.entries_with_prefix("MFRGGZC7MFRGGZC7MFRGGZC7MVTGO")
+                .count(),
+            1
+        );
+        assert_eq!(tree.entries_with_prefix("7").count(), 1);
+        assert_eq!(tree.entries_with_prefix("MFRGH").count(), 0);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "data-encoding")]
+    #[test]
+    fn test_with_prefix_any_case() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::encoding::Base32::<20>::new(scheme::Case::Any))
+            .with_prefix_part_lengths([3, 2])
+            .build()?;
+
+        drop(tree.create_file(*b"abcd_abcd_abcd_abcd_")?);
+        drop(tree.create_file([255; 20])?);
+
+        for prefix in [
+            "MFRGG",
+            "mfrgg",
+            "mFr",
+            "MFRGGZC7MFRGGZC7MFRGGZC7MFRGGZC7",
+            "mfrggzc7mf",
+        ] {
+            let names = tree
+                .entries_with_prefix(prefix)
+                .map(|entry| entry.map(|entry| entry.name))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            assert_eq!(names, vec![*b"abcd_abcd_abcd_abcd_"], "{prefix:?}");
+        }
+
+        assert_eq!(tree.entries_with_prefix("mfrgh").count(), 0);
+
+        Ok(())
+    }
+
+    /// Check descending iteration over names whose first byte is the same for each prefix part.
+    fn check_reverse<S>(
+        tree: &Tree<S>,
+        prefix: &str,
+        has_prefix: fn(&[u8; 5]) -> bool,
+    ) -> Result<(), Box<dyn std::error::Error>>
+    where
+        S: for<'a> scheme::Scheme<Name = [u8; 5], NameRef<'a> = [u8; 5]>,
+    {
+        let names = (0..100u8)
+            .map(|i| [i.wrapping_mul(73), i.wrapping_mul(29), i, 0, 0])
+            .collect::<Vec<_>>();
+
+        for name in &names {
+            drop(tree.create_file(*name)?.expect("Failed to create file"));
+        }
+
+        let mut sorted_names = names.clone();
+        sorted_names.sort_unstable_by(|a, b| b.cmp(a));
+
+        let result = tree
+            .entries_rev()
+            .map(|entry| entry.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(result, sorted_names);
+
+        let (start, end) = (names[10].min(names[60]), names[10].max(names[60]));
+        let expected = sorted_names
+            .iter()
+            .filter(|name| (start..=end).contains(*name))
+            .copied()
+            .collect::<Vec<_>>();
+        let result = tree
+            .entries_range(start..=end)
+            .reverse()
+            .map(|entry| entry.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert!(!expected.is_empty());
+        assert_eq!(result, expected);
+
+        let result = tree
+            .entries_rev()
+            .with_prefix(prefix)
+            .map(|entry| entry.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let expected = sorted_names
+            .iter()
+            .filter(|name| has_prefix(name))
+            .copied()
+            .collect::<Vec<_>>();
+        assert!(!expected.is_empty());
+        assert_eq!(result, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reverse() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::hex::Hex::<5>::default())
+            .with_prefix_part_lengths([1, 1])
+            .build()?;
+
+        check_reverse(&tree, "0", |name| name[0] < 16)
+    }
+
+    #[cfg(feature = "data-encoding")]
+    #[test]
+    fn test_reverse_base32() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::encoding::Base32::<5>::new(scheme::Case::Upper))
+            .with_prefix_part_lengths([1, 1])
+            .build()?;
+
+        check_reverse(&tree, "A", |name| name[0] < 8)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symbolic_links_are_followed() -> Result<(), Box<dyn std::error::Error>> {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = tempfile::tempdir()?;
+        let other_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([2])
+            .build()?;
+
+        drop(tree.create_file("abcd")?);
+
+        std::fs::create_dir_all(other_dir.path().join("ef"))?;
+        std::fs::write(other_dir.path().join("ef/efgh"), b"")?;
+        std::fs::write(other_dir.path().join("abxy"), b"")?;
+        symlink(other_dir.path().join("ef"), temp_dir.path().join("ef"))?;
+        symlink(
+            other_dir.path().join("abxy"),
+            temp_dir.path().join("ab/abxy"),
+        )?;
+        symlink(
+            other_dir.path().join("missing"),
+            temp_dir.path().join("ab/abzz"),
+        )?;
+
+        let mut entries = tree.entries().lenient();
+        let names = entries
+            .by_ref()
+            .map(|entry| entry.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(names, vec!["abcd", "abxy", "efgh"]);
+        assert_eq!(entries.skipped().len(), 1);
+        assert!(matches!(
+            entries.skipped()[0].error,
+            Error::ExpectedFile(ref path) if path.ends_with("ab/abzz")
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_metadata() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([1])
+            .with_extension("txt")
+            .build()?;
+
+        for (name, contents) in [("abc", "a"), ("bcd", "bb"), ("cde", "ccc")] {
+            tree.create_file(name)?
+                .expect("Failed to create file")
+                .write_all(contents.as_bytes())?;
+        }
+
+        let sizes = tree
+            .entries_with_metadata()
+            .map(|result| result.map(|(entry, metadata)| (entry.name, metadata.len())))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(
+            sizes,
+            vec![
+                ("abc".to_string(), 1),
+                ("bcd".to_string(), 2),
+                ("cde".to_string(), 3)
+            ]
+        );
+
+        File::create(temp_dir.path().join("b/bcd.md"))?;
+
+        let mut entries = tree.entries().lenient().with_metadata();
+
+        assert_eq!(entries.by_ref().count(), 3);
+        assert_eq!(entries.skipped().len(), 1);
+        assert_eq!(
+            entries.checkpoint().map(super::Checkpoint::as_str),
+            Some("cde")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resume_after_checkpoint() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::hex::Hex::<2>::default())
+            .with_prefix_part_lengths([2])
+            .build()?;
+
+        for i in (0..200u16).step_by(2) {
+            drop(tree.create_file(i.wrapping_mul(257).to_be_bytes())?);
+        }
+
+        let mut entries = tree.entries();
+        let first = entries
+            .by_ref()
+            .take(40)
+            .map(|entry| entry.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let checkpoint = entries
+            .checkpoint()
+            .expect("Expected checkpoint")
+            .to_string();
+
+        drop(entries);
+
+        // Add names on both sides of the checkpoint.
+        let checkpoint = checkpoint.parse::<super::Checkpoint>()?;
+        drop(tree.create_file([0, 1])?);
+        drop(tree.create_file([255, 255])?);
+
+        let mut entries = tree.entries_after(&checkpoint);
+
+        assert_eq!(entries.checkpoint(), Some(&checkpoint));
+
+        let rest = entries
+            .by_ref()
+            .map(|entry| entry.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(
+            entries.checkpoint().map(super::Checkpoint::as_str),
+            Some("ffff")
+        );
+        assert_eq!(first.len() + rest.len(), 101);
+        assert!(!rest.contains(&[0, 1]));
+        assert!(rest.contains(&[255, 255]));
+
+        let all = first.iter().chain(&rest).copied().collect::<Vec<_>>();
+        let expected = tree
+            .entries()
+            .map(|entry| entry.map(|entry| entry.name))
+            .filter(|name| !matches!(name, Ok([0, 1])))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(all, expected);
+
+        // Resuming works in descending order and with ranges.
+        let mut entries = tree.entries_range([0x20, 0]..[0xa0, 0]).reverse();
+        let first = entries
+            .by_ref()
+            .take(10)
+            .map(|entry| entry.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let checkpoint = entries.checkpoint().cloned().expect("Expected checkpoint");
+        let rest = tree
+            .entries_range([0x20, 0]..[0xa0, 0])
+            .resume_after(&checkpoint)
+            .reverse()
+            .map(|entry| entry.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let all = first.into_iter().chain(rest).collect::<Vec<_>>();
+        let expected = tree
+            .entries_range([0x20, 0]..[0xa0, 0])
+            .reverse()
+            .map(|entry| entry.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(all, expected);
+
+        Ok(())
+    }
+}
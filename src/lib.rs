@@ -1,13 +1,26 @@
 #![warn(clippy::all, clippy::pedantic, clippy::nursery, rust_2018_idioms)]
 #![allow(clippy::missing_errors_doc)]
 #![forbid(unsafe_code)]
+use std::ffi::{OsStr, OsString};
 use std::fs::File;
+use std::ops::{Bound, RangeBounds};
 use std::path::{Path, PathBuf};
 
+pub mod atomic;
 pub mod builder;
+pub mod check;
 pub mod constraint;
+pub mod infer;
 pub mod iter;
+pub mod manifest;
+#[cfg(feature = "rayon")]
+pub mod par;
+pub mod repair;
 pub mod scheme;
+pub mod shard;
+pub mod stats;
+#[cfg(test)]
+mod test_support;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -61,32 +74,141 @@ impl<S> Tree<S> {
             },
         ))
     }
+
+    pub(crate) fn validate_extension<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<(), Option<OsString>> {
+        match &self.extension_constraint {
+            None => Ok(()),
+            Some(crate::constraint::Extension::None) => path
+                .as_ref()
+                .extension()
+                .map_or(Ok(()), |extension| Err(Some(extension.to_os_string()))),
+            Some(crate::constraint::Extension::Any) => {
+                path.as_ref().extension().map_or(Err(None), |_| Ok(()))
+            }
+            Some(crate::constraint::Extension::Fixed(expected_extension)) => {
+                path.as_ref().extension().map_or(Err(None), |extension| {
+                    if **expected_extension == *extension {
+                        Ok(())
+                    } else {
+                        Err(Some(extension.to_os_string()))
+                    }
+                })
+            }
+        }
+    }
 }
 
 impl<S: scheme::Scheme> Tree<S> {
+    pub(crate) fn validate_file_stem_length<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<(), Option<usize>> {
+        match &self.length_constraint {
+            None => Ok(()),
+            Some(crate::constraint::Length::Fixed(length)) => {
+                self.file_stem_length(path)
+                    .map_or(Err(None), |file_stem_length| {
+                        if file_stem_length == *length {
+                            Ok(())
+                        } else {
+                            Err(Some(file_stem_length))
+                        }
+                    })
+            }
+            Some(crate::constraint::Length::Range(minimum, maximum)) => self
+                .file_stem_length(path)
+                .map_or(Err(None), |file_stem_length| {
+                    if file_stem_length >= *minimum && file_stem_length < *maximum {
+                        Ok(())
+                    } else {
+                        Err(Some(file_stem_length))
+                    }
+                }),
+        }
+    }
+
+    /// The length of the path's file stem in the scheme's prefix unit.
+    fn file_stem_length<P: AsRef<Path>>(&self, path: P) -> Option<usize> {
+        path.as_ref()
+            .file_stem()
+            .and_then(|file_stem| self.scheme.prefix_unit().length(file_stem))
+    }
+
+    /// Return the path at which a file with the given path's file name should be stored.
+    ///
+    /// The result will be empty if the file stem cannot be split into the prefix parts.
+    pub(crate) fn expected_path(&self, path: &Path) -> Option<PathBuf> {
+        let file_stem = path.file_stem()?;
+        let mut expected = self.name_string_path(&file_stem.to_string_lossy())?;
+
+        if let Some(file_name) = path.file_name() {
+            expected.set_file_name(file_name);
+        }
+
+        Some(expected)
+    }
+
     /// Return the path through the tree for the given name.
     ///
     /// Note that this function ignores any configured extension constraint, or any extension at
     /// for a file with this file stem at the specified directory.
+    ///
+    /// The name and each prefix part are checked with `scheme::validate_path_component`, so the
+    /// result is always a path inside the base directory, and names in the reserved namespace
+    /// (see `RESERVED_PREFIX`) are rejected.
+    ///
+    /// Names whose string doesn't satisfy the scheme's own length constraint are also rejected,
+    /// since a file with that name could never be read back.
     fn name_path(&self, name: S::NameRef<'_>) -> Result<PathBuf, String> {
         let name_string = self.scheme.name_to_string(name);
 
-        if name_string.len() >= self.prefix_part_lengths_total().max(1) {
-            let mut name_remaining = name_string.as_ref();
+        self.scheme
+            .length_constraint()
+            .is_none_or(|length_constraint| {
+                self.scheme
+                    .prefix_unit()
+                    .length(OsStr::new(name_string.as_ref()))
+                    .is_some_and(|length| length_constraint.contains(length))
+            })
+            .then(|| self.name_string_path(&name_string))
+            .flatten()
+            .ok_or_else(|| name_string.to_string())
+    }
+
+    /// Return the path through the tree for the given name string (without any extension).
+    pub(crate) fn name_string_path(&self, name_string: &str) -> Option<PathBuf> {
+        let prefix_unit = self.scheme.prefix_unit();
+
+        if prefix_unit
+            .length(OsStr::new(name_string))
+            .is_some_and(|length| length >= self.prefix_part_lengths_total().max(1))
+            && scheme::validate_path_component(name_string).is_ok()
+            && !is_reserved(OsStr::new(name_string))
+        {
+            let mut name_remaining = name_string;
             let mut path = self.base.clone();
 
             for prefix_part_length in &self.prefix_part_lengths {
-                let next = &name_remaining[0..*prefix_part_length];
-                name_remaining = &name_remaining[*prefix_part_length..];
+                let (next, rest) = prefix_unit.split_at(name_remaining, *prefix_part_length)?;
+
+                scheme::validate_path_component(next).ok()?;
 
+                if is_reserved(OsStr::new(next)) {
+                    return None;
+                }
+
+                name_remaining = rest;
                 path.push(next);
             }
 
-            path.push(name_string.as_ref());
+            path.push(name_string);
 
-            Ok(path)
+            Some(path)
         } else {
-            Err(name_string.to_string())
+            None
         }
     }
 
@@ -133,11 +255,7 @@ impl<S: scheme::Scheme> Tree<S> {
     pub fn create_file(&self, name: S::NameRef<'_>) -> Result<Option<File>, Error> {
         let path = self.path(name).map_err(Error::InvalidName)?;
 
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-
-        match File::create_new(path) {
+        match with_parent_directories(&path, |path| File::create_new(path)) {
             Ok(file) => {
                 file.lock()?;
 
@@ -148,10 +266,149 @@ impl<S: scheme::Scheme> Tree<S> {
         }
     }
 
+    /// Try to remove the file for the given name, including any fixed extension.
+    ///
+    /// Returns `false` if there was no file to remove. Any prefix directories that are left empty
+    /// are also removed (the base directory is never removed).
+    ///
+    /// Note that this function will probably not do the right thing for any extension
+    /// configuration that does not either prohibit extensions or require a fixed extension.
+    pub fn remove_file(&self, name: S::NameRef<'_>) -> Result<bool, Error> {
+        let path = self.path(name).map_err(Error::InvalidName)?;
+
+        match std::fs::remove_file(&path) {
+            Ok(()) => {
+                self.prune_prefix_directories(&path)?;
+
+                Ok(true)
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(_) if path.is_dir() => Err(Error::ExpectedFile(path)),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Remove empty prefix directories above the given path, stopping at the first non-empty one.
+    ///
+    /// Only empty directories are removed, so a concurrent `create_file` can at worst see its
+    /// parent directory disappear, in which case it recreates it.
+    fn prune_prefix_directories(&self, path: &Path) -> Result<(), Error> {
+        let mut current = path.parent();
+
+        for _ in 0..self.prefix_part_lengths.len() {
+            match current {
+                Some(directory) if directory != self.base => match std::fs::remove_dir(directory) {
+                    Ok(()) => {
+                        current = directory.parent();
+                    }
+                    Err(error)
+                        if matches!(
+                            error.kind(),
+                            std::io::ErrorKind::DirectoryNotEmpty | std::io::ErrorKind::NotFound
+                        ) =>
+                    {
+                        break;
+                    }
+                    Err(error) => return Err(error.into()),
+                },
+                _ => break,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Start an atomic write for the given name, including any fixed extension.
+    ///
+    /// The contents are written to a staging file inside the tree and only become visible at the
+    /// entry's path when the returned guard is committed.
+    pub fn write_atomic(
+        &self,
+        name: S::NameRef<'_>,
+        policy: atomic::Policy,
+    ) -> Result<atomic::AtomicFile, Error> {
+        let path = self.path(name).map_err(Error::InvalidName)?;
+
+        atomic::AtomicFile::create(path, policy)
+    }
+
+    /// Atomically write the given bytes as the contents for the given name.
+    ///
+    /// Returns `false` if the policy is `atomic::Policy::Refuse` and an entry already existed.
+    pub fn put<B: AsRef<[u8]>>(
+        &self,
+        name: S::NameRef<'_>,
+        bytes: B,
+        policy: atomic::Policy,
+    ) -> Result<bool, Error> {
+        let mut file = self.write_atomic(name, policy)?;
+
+        std::io::Write::write_all(&mut file, bytes.as_ref())?;
+
+        file.commit()
+    }
+
     #[must_use]
     pub fn entries(&self) -> iter::Entries<'_, S> {
         iter::Entries::new(self)
     }
+
+    /// Iterate over the entries together with the metadata of their files.
+    ///
+    /// See `iter::Entries::with_metadata` for details.
+    #[must_use]
+    pub fn entries_with_metadata(&self) -> iter::WithMetadata<'_, S> {
+        self.entries().with_metadata()
+    }
+
+    /// Iterate over the entries in descending order.
+    ///
+    /// See `iter::Entries::reverse` for details.
+    #[must_use]
+    pub fn entries_rev(&self) -> iter::Entries<'_, S> {
+        self.entries().reverse()
+    }
+
+    /// Iterate over the entries with names starting from the given name (inclusive).
+    ///
+    /// See `iter::Entries::range` for details.
+    #[must_use]
+    pub fn entries_from(&self, name: S::NameRef<'_>) -> iter::Entries<'_, S> {
+        let lower = self.scheme.name_to_string(name).into_owned();
+
+        self.entries()
+            .with_bounds(Bound::Included(lower), Bound::Unbounded)
+    }
+
+    /// Iterate over the entries after the given checkpoint.
+    ///
+    /// See `iter::Entries::resume_after` for details.
+    #[must_use]
+    pub fn entries_after(&self, checkpoint: &iter::Checkpoint) -> iter::Entries<'_, S> {
+        self.entries().resume_after(checkpoint)
+    }
+
+    /// Iterate over the entries with name strings that start with the given prefix.
+    ///
+    /// See `iter::Entries::with_prefix` for details.
+    #[must_use]
+    pub fn entries_with_prefix(&self, prefix: &str) -> iter::Entries<'_, S> {
+        self.entries().with_prefix(prefix)
+    }
+
+    /// Iterate over the entries with names in the given range.
+    ///
+    /// See `iter::Entries::range` for details.
+    #[must_use]
+    pub fn entries_range<'n, R: RangeBounds<S::NameRef<'n>>>(
+        &self,
+        range: R,
+    ) -> iter::Entries<'_, S>
+    where
+        S::NameRef<'n>: Clone,
+    {
+        self.entries().range(range)
+    }
 }
 
 impl Tree<scheme::Identity> {
@@ -166,9 +423,7 @@ impl Tree<scheme::Identity> {
     /// If this function returns a result, it is guaranteed to be correct if the store is valid, but the validity is not checked.
     pub fn infer_prefix_part_lengths<P: AsRef<Path>>(base: P) -> Result<Option<Vec<usize>>, Error> {
         if base.as_ref().is_dir() {
-            let first = std::fs::read_dir(base)?
-                .next()
-                .map_or(Ok(None), |entry| entry.map(|entry| Some(entry.path())))?;
+            let first = Self::first_unreserved_path(base)?;
 
             let mut acc = vec![];
 
@@ -197,9 +452,7 @@ impl Tree<scheme::Identity> {
 
             acc.push(directory_name.len());
 
-            let next = std::fs::read_dir(current)?
-                .next()
-                .map_or(Ok(None), |entry| entry.map(|entry| Some(entry.path())))?;
+            let next = Self::first_unreserved_path(current)?;
 
             next.map_or(Ok(true), |next| {
                 Self::infer_prefix_part_lengths_rec(next, acc)
@@ -207,6 +460,19 @@ impl Tree<scheme::Identity> {
         }
     }
 
+    /// Return the path of the first directory entry that is not reserved (see `RESERVED_PREFIX`).
+    fn first_unreserved_path<P: AsRef<Path>>(path: P) -> Result<Option<PathBuf>, Error> {
+        for result in std::fs::read_dir(path)? {
+            let entry = result?;
+
+            if !is_reserved(&entry.file_name()) {
+                return Ok(Some(entry.path()));
+            }
+        }
+
+        Ok(None)
+    }
+
     /// Infer the extension constraint used to create a store.
     ///
     /// The result will be empty if and only if the store has no files (even if there are directories).
@@ -237,6 +503,10 @@ impl Tree<scheme::Identity> {
             for result in std::fs::read_dir(current)? {
                 let entry = result?;
 
+                if is_reserved(&entry.file_name()) {
+                    continue;
+                }
+
                 if let Some(constraint) = Self::infer_extension_constraint(entry.path())? {
                     return Ok(Some(constraint));
                 }
@@ -247,6 +517,48 @@ impl Tree<scheme::Identity> {
     }
 }
 
+/// Prefix for the names of files and directories that the library itself creates inside a tree.
+///
+/// Anything with a name starting with this prefix is ignored during iteration.
+const RESERVED_PREFIX: &str = ".prefix-file-tree";
+
+fn is_reserved(file_name: &OsStr) -> bool {
+    file_name
+        .as_encoded_bytes()
+        .starts_with(RESERVED_PREFIX.as_bytes())
+}
+
+/// Maximum number of times to recreate parent directories that are concurrently removed.
+const PARENT_DIRECTORY_ATTEMPTS: usize = 8;
+
+/// Create the parent directories of the given path and then apply the given operation to it.
+///
+/// If the operation fails because a parent directory was removed in the meantime (e.g. by
+/// `Tree::remove_file` pruning empty prefix directories), the directories are recreated and the
+/// operation is retried.
+fn with_parent_directories<T, F: Fn(&Path) -> std::io::Result<T>>(
+    path: &Path,
+    operation: F,
+) -> std::io::Result<T> {
+    let mut attempts = 0;
+
+    loop {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        match operation(path) {
+            Err(error)
+                if error.kind() == std::io::ErrorKind::NotFound
+                    && attempts < PARENT_DIRECTORY_ATTEMPTS =>
+            {
+                attempts += 1;
+            }
+            other => return other,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -296,6 +608,129 @@ mod tests {
         assert_eq!(result.unwrap_err(), "abc");
     }
 
+    #[test]
+    fn test_path_splits_utf8_on_characters() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([1, 2])
+            .with_length(6)
+            .build()?;
+
+        let path = tree.path("éclair")?;
+        assert!(path.ends_with("é/cl/éclair"));
+
+        // Three characters are enough, even though the name is four bytes long.
+        assert!(tree.path("éé").is_err());
+        assert!(tree.path("ééé").is_ok());
+
+        drop(tree.create_file("éclair")?.expect("Failed to create file"));
+        drop(tree.create_file("ascii!")?.expect("Failed to create file"));
+
+        let entries = tree.entries().collect::<Result<Vec<_>, _>>()?;
+        let names = entries
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["ascii!", "éclair"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_path_rejects_byte_split_inside_character() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_prefix_part_lengths([1])
+            .build()?;
+
+        let result = tree.path(OsStr::new("éclair"));
+        assert_eq!(result, Err("éclair".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_path_never_escapes_base() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let names = [
+            "",
+            ".",
+            "..",
+            "...",
+            "../../etc/passwd",
+            "..abc",
+            ".abc",
+            "./abc",
+            "a/b",
+            "ab/cd",
+            "/etc/passwd",
+            "/",
+            "//",
+            "abc/",
+            "a\0bc",
+            "abc\0",
+            "abcdef",
+            ".prefix-file-tree",
+            ".prefix-file-tree-staging-abc",
+        ];
+
+        for prefix_part_lengths in [&[][..], &[1], &[2], &[1, 1], &[2, 2], &[3]] {
+            let tree = Tree::builder(temp_dir.path())
+                .with_scheme(scheme::Utf8)
+                .with_prefix_part_lengths(prefix_part_lengths)
+                .build()?;
+
+            for name in names {
+                if let Ok(path) = tree.path(name) {
+                    assert!(!name.starts_with(RESERVED_PREFIX), "{name:?}");
+
+                    let relative = path.strip_prefix(temp_dir.path())?;
+                    let components = relative.components().collect::<Vec<_>>();
+
+                    assert_eq!(components.len(), prefix_part_lengths.len() + 1, "{name:?}");
+                    assert!(
+                        components
+                            .iter()
+                            .all(|component| matches!(component, std::path::Component::Normal(_))),
+                        "{name:?} resolved to {path:?}"
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_file_rejects_escaping_names() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let base = temp_dir.path().join("base");
+        let tree = Tree::builder(&base)
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([2])
+            .build()?;
+
+        for name in [
+            "../escaped",
+            "..escaped",
+            "ab/../../escaped",
+            "/tmp/escaped",
+            ".prefix-file-tree",
+            ".prefix-file-tree-lost+found",
+        ] {
+            match tree.create_file(name) {
+                Err(Error::InvalidName(invalid_name)) => assert_eq!(invalid_name, name),
+                other => panic!("Expected `Err(InvalidName)` for {name:?}, got {other:?}"),
+            }
+        }
+
+        assert!(!base.exists());
+        assert_eq!(std::fs::read_dir(temp_dir.path())?.count(), 0);
+
+        Ok(())
+    }
+
     #[test]
     fn test_open_file_nonexistent() -> Result<(), Box<dyn std::error::Error>> {
         let temp_dir = tempfile::tempdir()?;
@@ -448,6 +883,75 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_remove_file_prunes_empty_prefix_directories() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([2, 2])
+            .build()?;
+
+        drop(tree.create_file("abcdef")?.expect("Failed to create file"));
+        drop(tree.create_file("abxyzw")?.expect("Failed to create file"));
+
+        assert!(tree.remove_file("abcdef")?);
+        assert!(!temp_dir.path().join("ab/cd").exists());
+        assert!(temp_dir.path().join("ab/xy/abxyzw").is_file());
+
+        assert!(tree.remove_file("abxyzw")?);
+        assert!(!temp_dir.path().join("ab").exists());
+        assert!(temp_dir.path().is_dir());
+
+        // The file has already been removed.
+        assert!(!tree.remove_file("abxyzw")?);
+
+        // The directories are recreated as needed.
+        assert!(tree.create_file("abcdef")?.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_file_with_fixed_extension() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([1])
+            .with_extension("txt")
+            .build()?;
+
+        drop(tree.create_file("abc")?.expect("Failed to create file"));
+        std::fs::File::create(temp_dir.path().join("a/abd"))?;
+
+        assert!(tree.remove_file("abc")?);
+        assert!(!temp_dir.path().join("a/abc.txt").exists());
+
+        // The directory still contains a file without the extension.
+        assert!(!tree.remove_file("abd")?);
+        assert!(temp_dir.path().join("a/abd").is_file());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_file_directory_instead_of_file() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([1])
+            .build()?;
+
+        std::fs::create_dir_all(temp_dir.path().join("a/abc"))?;
+
+        match tree.remove_file("abc") {
+            Err(Error::ExpectedFile(_)) => {}
+            other => panic!("Expected `Err(ExpectedFile)`, got {other:?}"),
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_infer_empty_directory() -> Result<(), Box<dyn std::error::Error>> {
         let temp_dir = tempfile::tempdir()?;
@@ -678,4 +1182,31 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_open_inferred_ignores_reserved_names() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::hex::Hex::<3>::default())
+            .with_prefix_part_lengths([2, 2])
+            .with_extension("bin")
+            .build_with_manifest()?;
+
+        drop(tree.create_file([1, 2, 3])?);
+
+        let lost_and_found = temp_dir.path().join(".prefix-file-tree-lost+found/00");
+        std::fs::create_dir_all(&lost_and_found)?;
+        std::fs::File::create(lost_and_found.join("000000"))?;
+
+        let inferred = Tree::open_inferred(temp_dir.path(), scheme::hex::Hex::<3>::default())?
+            .expect("Expected at least one file");
+
+        assert_eq!(inferred.prefix_part_lengths, vec![2, 2]);
+        assert_eq!(
+            inferred.extension_constraint,
+            Some(crate::constraint::Extension::Fixed("bin".to_string()))
+        );
+
+        Ok(())
+    }
 }
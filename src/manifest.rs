@@ -0,0 +1,308 @@
+use crate::{Tree, builder, constraint, scheme::Scheme};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Name of the manifest file in the base directory (within the names reserved by
+/// `crate::RESERVED_PREFIX`).
+pub(crate) const MANIFEST_FILE_NAME: &str = ".prefix-file-tree";
+
+const VERSION: &str = "1";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("I/O error")]
+    Io(#[from] std::io::Error),
+    #[error("Tree error")]
+    Tree(#[from] crate::Error),
+    #[error("Invalid tree configuration")]
+    Builder(#[from] builder::Error),
+    #[error("Invalid manifest line")]
+    InvalidLine(usize),
+    #[error("Unsupported manifest version")]
+    UnsupportedVersion(String),
+    #[error("Scheme mismatch")]
+    SchemeMismatch {
+        expected: Option<String>,
+        found: Option<String>,
+    },
+}
+
+/// The layout of a tree, as recorded in the manifest file at its base.
+///
+/// The string representation has one `key = value` line per field, where prefix part lengths are
+/// separated by spaces, length ranges are written as `2..10`, and extension constraints are
+/// written as `none`, `any`, or a fixed extension with a leading period (e.g. `.txt`).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Manifest {
+    pub scheme: Option<String>,
+    pub prefix_part_lengths: Vec<usize>,
+    pub length_constraint: Option<constraint::Length>,
+    pub extension_constraint: Option<constraint::Extension>,
+}
+
+impl std::fmt::Display for Manifest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "version = {VERSION}")?;
+
+        if let Some(scheme) = &self.scheme {
+            writeln!(f, "scheme = {scheme}")?;
+        }
+
+        let mut prefix_part_lengths = String::new();
+
+        for (i, prefix_part_length) in self.prefix_part_lengths.iter().enumerate() {
+            if i > 0 {
+                prefix_part_lengths.push(' ');
+            }
+
+            write!(prefix_part_lengths, "{prefix_part_length}")?;
+        }
+
+        writeln!(f, "prefix-part-lengths = {prefix_part_lengths}")?;
+
+        match self.length_constraint {
+            Some(constraint::Length::Fixed(length)) => writeln!(f, "length = {length}")?,
+            Some(constraint::Length::Range(minimum, maximum)) => {
+                writeln!(f, "length = {minimum}..{maximum}")?;
+            }
+            None => {}
+        }
+
+        match &self.extension_constraint {
+            Some(constraint::Extension::None) => writeln!(f, "extension = none")?,
+            Some(constraint::Extension::Any) => writeln!(f, "extension = any")?,
+            Some(constraint::Extension::Fixed(extension)) => {
+                writeln!(f, "extension = .{extension}")?;
+            }
+            None => {}
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Manifest {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut manifest = Self::default();
+        let mut version = None;
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid_line = || Error::InvalidLine(i + 1);
+            let (key, value) = line.split_once('=').ok_or_else(invalid_line)?;
+            let value = value.trim();
+
+            match key.trim() {
+                "version" => {
+                    version = Some(value.to_string());
+                }
+                "scheme" => {
+                    manifest.scheme = Some(value.to_string());
+                }
+                "prefix-part-lengths" => {
+                    manifest.prefix_part_lengths = value
+                        .split_whitespace()
+                        .map(str::parse)
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| invalid_line())?;
+                }
+                "length" => {
+                    manifest.length_constraint = Some(match value.split_once("..") {
+                        Some((minimum, maximum)) => constraint::Length::Range(
+                            minimum.parse().map_err(|_| invalid_line())?,
+                            maximum.parse().map_err(|_| invalid_line())?,
+                        ),
+                        None => {
+                            constraint::Length::Fixed(value.parse().map_err(|_| invalid_line())?)
+                        }
+                    });
+                }
+                "extension" => {
+                    manifest.extension_constraint = Some(match value {
+                        "none" => constraint::Extension::None,
+                        "any" => constraint::Extension::Any,
+                        _ => constraint::Extension::Fixed(
+                            value
+                                .strip_prefix('.')
+                                .filter(|extension| !extension.is_empty())
+                                .ok_or_else(invalid_line)?
+                                .to_string(),
+                        ),
+                    });
+                }
+                _ => {
+                    return Err(invalid_line());
+                }
+            }
+        }
+
+        match version {
+            Some(version) if version == VERSION => Ok(manifest),
+            Some(version) => Err(Error::UnsupportedVersion(version)),
+            None => Err(Error::UnsupportedVersion(String::new())),
+        }
+    }
+}
+
+impl<S: Scheme> Tree<S> {
+    /// Open a tree with the layout recorded in the manifest file at its base.
+    ///
+    /// Fails if the manifest doesn't exist, or if it records a different scheme identifier from
+    /// the given scheme's (see `Scheme::identifier`).
+    pub fn open<P: AsRef<Path>>(base: P, scheme: S) -> Result<Self, Error> {
+        let manifest =
+            std::fs::read_to_string(base.as_ref().join(MANIFEST_FILE_NAME))?.parse::<Manifest>()?;
+
+        if let Some(expected) = manifest.scheme {
+            let found = scheme.identifier();
+
+            if found.as_deref() != Some(expected.as_str()) {
+                return Err(Error::SchemeMismatch {
+                    expected: Some(expected),
+                    found: found.map(std::borrow::Cow::into_owned),
+                });
+            }
+        }
+
+        let builder = Tree::builder(base)
+            .with_scheme(scheme)
+            .with_prefix_part_lengths(manifest.prefix_part_lengths);
+
+        let builder = match manifest.length_constraint {
+            Some(constraint::Length::Fixed(length)) => builder.with_length(length),
+            Some(constraint::Length::Range(minimum, maximum)) => {
+                builder.with_length_range(minimum..maximum)
+            }
+            None => builder,
+        };
+
+        let builder = match manifest.extension_constraint {
+            Some(constraint::Extension::None) => builder.with_no_extension(),
+            Some(constraint::Extension::Any) => builder.with_any_extension(),
+            Some(constraint::Extension::Fixed(extension)) => builder.with_extension(extension),
+            None => builder,
+        };
+
+        Ok(builder.build()?)
+    }
+
+    /// The layout of this tree.
+    #[must_use]
+    pub fn manifest(&self) -> Manifest {
+        Manifest {
+            scheme: self.scheme.identifier().map(std::borrow::Cow::into_owned),
+            prefix_part_lengths: self.prefix_part_lengths.clone(),
+            length_constraint: self.length_constraint,
+            extension_constraint: self.extension_constraint.clone(),
+        }
+    }
+
+    /// Atomically write (or replace) the manifest file at the base of the tree.
+    pub fn write_manifest(&self) -> Result<(), crate::Error> {
+        let mut file = crate::atomic::AtomicFile::create(
+            self.manifest_path(),
+            crate::atomic::Policy::Replace,
+        )?;
+
+        std::io::Write::write_all(&mut file, self.manifest().to_string().as_bytes())?;
+        file.commit()?;
+
+        Ok(())
+    }
+
+    /// The path of the manifest file.
+    #[must_use]
+    pub fn manifest_path(&self) -> PathBuf {
+        self.base.join(MANIFEST_FILE_NAME)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, Manifest};
+    use crate::{Tree, constraint, scheme};
+
+    #[test]
+    fn test_manifest_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let manifest = Manifest {
+            scheme: Some("hex-3-lower".to_string()),
+            prefix_part_lengths: vec![2, 2],
+            length_constraint: Some(constraint::Length::Range(4, 10)),
+            extension_constraint: Some(constraint::Extension::Fixed("txt".to_string())),
+        };
+
+        assert_eq!(
+            manifest.to_string(),
+            "version = 1\nscheme = hex-3-lower\nprefix-part-lengths = 2 2\nlength = 4..10\nextension = .txt\n"
+        );
+        assert_eq!(manifest.to_string().parse::<Manifest>()?, manifest);
+
+        let manifest = Manifest::default();
+
+        assert_eq!(manifest.to_string().parse::<Manifest>()?, manifest);
+
+        assert!(matches!(
+            "version = 1\nfoo = bar\n".parse::<Manifest>(),
+            Err(Error::InvalidLine(2))
+        ));
+        assert!(matches!(
+            "version = 2\n".parse::<Manifest>(),
+            Err(Error::UnsupportedVersion(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_with_manifest_and_open() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::hex::Hex::<3>::default())
+            .with_prefix_part_lengths([2, 2])
+            .with_extension("bin")
+            .build_with_manifest()?;
+
+        drop(tree.create_file([1, 2, 3])?);
+
+        let opened = Tree::open(temp_dir.path(), scheme::hex::Hex::<3>::default())?;
+
+        assert_eq!(opened, tree);
+
+        // The manifest is not an entry, and it is not reported as a problem.
+        assert_eq!(opened.entries().count(), 1);
+        assert!(opened.check()?.is_consistent());
+
+        let result = Tree::open(
+            temp_dir.path(),
+            scheme::hex::Hex::<3>::new(scheme::Case::Upper),
+        );
+
+        assert!(matches!(
+            result,
+            Err(Error::SchemeMismatch { expected: Some(expected), found: Some(found) })
+                if expected == "hex-3-lower" && found == "hex-3-upper"
+        ));
+
+        assert!(matches!(
+            Tree::open(temp_dir.path(), scheme::Utf8),
+            Err(Error::SchemeMismatch { .. })
+        ));
+
+        let other_dir = tempfile::tempdir()?;
+
+        assert!(matches!(
+            Tree::open(other_dir.path(), scheme::Utf8),
+            Err(Error::Io(_))
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{Entry, Tree, iter, scheme::Scheme};
+use rayon::iter::{IntoParallelIterator, ParallelBridge, ParallelIterator};
+
+impl<S: Scheme + Sync> Tree<S>
+where
+    S::Name: Send,
+{
+    /// Iterate over the entries in parallel, with one task for each top-level prefix directory.
+    ///
+    /// Entries are produced in no particular order (even when collected). See
+    /// `par_entries_ordered` for a version that preserves the scheme's ordering.
+    pub fn par_entries(&self) -> impl ParallelIterator<Item = Result<Entry<S::Name>, iter::Error>> {
+        let (subtrees, error) = split(self.entries());
+
+        error.into_par_iter().map(Err).chain(
+            subtrees
+                .into_iter()
+                .par_bridge()
+                .flat_map_iter(|entries| entries),
+        )
+    }
+
+    /// Iterate over the entries in parallel, preserving the order of `entries` when collected.
+    ///
+    /// Like `par_entries`, this uses one task for each top-level prefix directory.
+    pub fn par_entries_ordered(
+        &self,
+    ) -> impl ParallelIterator<Item = Result<Entry<S::Name>, iter::Error>> {
+        let (subtrees, error) = split(self.entries());
+
+        error
+            .into_par_iter()
+            .map(Err)
+            .chain(subtrees.into_par_iter().flat_map_iter(|entries| entries))
+    }
+}
+
+/// Split the iteration by top-level prefix directory, keeping any error from listing the base.
+fn split<S: Scheme>(
+    entries: iter::Entries<'_, S>,
+) -> (Vec<iter::Entries<'_, S>>, Option<iter::Error>) {
+    match entries.into_subtrees() {
+        Ok(subtrees) => (subtrees, None),
+        Err(error) => (vec![], Some(error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Tree, scheme};
+    use rayon::iter::ParallelIterator;
+    use std::fs::File;
+
+    #[test]
+    fn test_par_entries() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::hex::Hex::<4>::default())
+            .with_prefix_part_lengths([1, 2])
+            .build()?;
+
+        for i in 0..500u32 {
+            drop(tree.create_file(i.wrapping_mul(2_654_435_761).to_be_bytes())?);
+        }
+
+        // An invalid prefix directory, which should be reported in order.
+        std::fs::create_dir(temp_dir.path().join("88"))?;
+        File::create(temp_dir.path().join("88/88000000"))?;
+
+        let expected = tree
+            .entries()
+            .map(|result| {
+                result
+                    .map(|entry| entry.name)
+                    .map_err(|error| format!("{error:?}"))
+            })
+            .collect::<Vec<_>>();
+
+        let ordered = tree
+            .par_entries_ordered()
+            .map(|result| {
+                result
+                    .map(|entry| entry.name)
+                    .map_err(|error| format!("{error:?}"))
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(ordered, expected);
+
+        let mut unordered = tree
+            .par_entries()
+            .map(|result| {
+                result
+                    .map(|entry| entry.name)
+                    .map_err(|error| format!("{error:?}"))
+            })
+            .collect::<Vec<_>>();
+        unordered.sort();
+
+        let mut expected = expected;
+        expected.sort();
+
+        assert_eq!(unordered, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_par_entries_without_prefix_parts() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::Utf8)
+            .build()?;
+
+        for name in ["foo", "bar", "baz"] {
+            drop(tree.create_file(name)?);
+        }
+
+        let names = tree
+            .par_entries_ordered()
+            .map(|result| result.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(names, vec!["bar", "baz", "foo"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_par_entries_missing_base() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path().join("missing"))
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([1])
+            .build()?;
+
+        let results = tree.par_entries().collect::<Vec<_>>();
+
+        assert!(matches!(
+            results.as_slice(),
+            [Err(crate::iter::Error::ExpectedDirectory(_))]
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,280 @@
+use crate::{Error, Tree, scheme::Scheme};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Name of the default quarantine directory, which is inside the base directory but ignored by
+/// iteration (see `crate::RESERVED_PREFIX`).
+const LOST_AND_FOUND: &str = ".prefix-file-tree-lost+found";
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Options {
+    dry_run: bool,
+    quarantine: Option<PathBuf>,
+}
+
+impl Options {
+    /// Only plan the repair, without changing anything on disk.
+    #[must_use]
+    pub fn with_dry_run(self) -> Self {
+        Self {
+            dry_run: true,
+            ..self
+        }
+    }
+
+    /// Move files that can't be repaired into the given directory.
+    ///
+    /// The directory should be on the same file system as the tree. By default a reserved
+    /// directory inside the base directory is used.
+    #[must_use]
+    pub fn with_quarantine<P: AsRef<Path>>(self, quarantine: P) -> Self {
+        Self {
+            quarantine: Some(quarantine.as_ref().to_path_buf()),
+            ..self
+        }
+    }
+}
+
+/// A single change made (or planned) by a repair.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Action {
+    /// Move a misplaced file to the path its file stem implies.
+    Move { from: PathBuf, to: PathBuf },
+    /// Move an invalid file or other entry into the quarantine directory.
+    Quarantine { from: PathBuf, to: PathBuf },
+    /// Remove a staging file left behind by an uncommitted atomic write.
+    RemoveFile(PathBuf),
+    /// Remove an empty directory.
+    RemoveDirectory(PathBuf),
+}
+
+impl<S: Scheme> Tree<S> {
+    /// Repair the problems found by `Tree::check`, returning the actions taken in order.
+    ///
+    /// Misplaced files are moved to the right prefix directories (or quarantined if an entry
+    /// already exists there), files and other entries that are invalid for the tree are moved to
+    /// the quarantine directory (keeping their path relative to the base), leftover staging files
+    /// are removed, and then any directories that are empty are removed.
+    ///
+    /// Nothing is ever deleted except staging files and empty directories. This function must not
+    /// be run concurrently with writes to the tree.
+    pub fn repair(&self, options: Options) -> Result<Vec<Action>, Error> {
+        let report = self.check()?;
+        let quarantine = options
+            .quarantine
+            .unwrap_or_else(|| self.base.join(LOST_AND_FOUND));
+
+        let mut actions = vec![];
+        let mut destinations = HashSet::new();
+
+        for misplaced in report.misplaced_files {
+            if misplaced.expected.exists() || destinations.contains(&misplaced.expected) {
+                let to = self.quarantine_path(&quarantine, &misplaced.path, &destinations)?;
+                destinations.insert(to.clone());
+
+                actions.push(Action::Quarantine {
+                    from: misplaced.path,
+                    to,
+                });
+            } else {
+                destinations.insert(misplaced.expected.clone());
+
+                actions.push(Action::Move {
+                    from: misplaced.path,
+                    to: misplaced.expected,
+                });
+            }
+        }
+
+        for path in report
+            .invalid_extensions
+            .into_iter()
+            .chain(report.invalid_file_stem_lengths)
+            .chain(
+                report
+                    .undecodable_file_stems
+                    .into_iter()
+                    .map(|(path, _)| path),
+            )
+            .chain(report.unexpected_entries)
+        {
+            let to = self.quarantine_path(&quarantine, &path, &destinations)?;
+            destinations.insert(to.clone());
+
+            actions.push(Action::Quarantine { from: path, to });
+        }
+
+        actions.extend(report.staging_files.into_iter().map(Action::RemoveFile));
+
+        let vacated = actions
+            .iter()
+            .map(|action| match action {
+                Action::Move { from, .. } | Action::Quarantine { from, .. } => from.clone(),
+                Action::RemoveFile(path) | Action::RemoveDirectory(path) => path.clone(),
+            })
+            .collect::<HashSet<_>>();
+
+        plan_directory_removals(&self.base, &vacated, &destinations, &mut actions)?;
+
+        if !options.dry_run {
+            for action in &actions {
+                match action {
+                    Action::Move { from, to } | Action::Quarantine { from, to } => {
+                        if let Some(parent) = to.parent() {
+                            std::fs::create_dir_all(parent)?;
+                        }
+
+                        std::fs::rename(from, to)?;
+                    }
+                    Action::RemoveFile(path) => std::fs::remove_file(path)?,
+                    Action::RemoveDirectory(path) => std::fs::remove_dir(path)?,
+                }
+            }
+        }
+
+        Ok(actions)
+    }
+
+    /// Pick a path in the quarantine directory that isn't already used.
+    fn quarantine_path(
+        &self,
+        quarantine: &Path,
+        path: &Path,
+        destinations: &HashSet<PathBuf>,
+    ) -> Result<PathBuf, Error> {
+        let relative = path
+            .strip_prefix(&self.base)
+            .map_err(|_| Error::InvalidFile(path.to_path_buf()))?;
+        let candidate = quarantine.join(relative);
+
+        let mut result = candidate.clone();
+        let mut suffix = 0;
+
+        while result.exists() || destinations.contains(&result) {
+            suffix += 1;
+            result.clone_from(&candidate);
+            result.add_extension(suffix.to_string());
+        }
+
+        Ok(result)
+    }
+}
+
+/// Add removals for directories that are (or will be, after the planned actions) empty.
+///
+/// Returns whether the given directory will be empty.
+fn plan_directory_removals(
+    path: &Path,
+    vacated: &HashSet<PathBuf>,
+    destinations: &HashSet<PathBuf>,
+    actions: &mut Vec<Action>,
+) -> Result<bool, Error> {
+    let mut is_empty = !destinations
+        .iter()
+        .any(|destination| destination.starts_with(path));
+
+    for entry in std::fs::read_dir(path)? {
+        let entry_path = entry?.path();
+
+        if !vacated.contains(&entry_path) {
+            // Symbolic links are never followed here, and reserved directories are kept.
+            let is_prefix_directory = entry_path
+                .file_name()
+                .is_some_and(|file_name| !crate::is_reserved(file_name))
+                && std::fs::symlink_metadata(&entry_path)?.is_dir();
+
+            if is_prefix_directory
+                && plan_directory_removals(&entry_path, vacated, destinations, actions)?
+            {
+                actions.push(Action::RemoveDirectory(entry_path));
+            } else {
+                is_empty = false;
+            }
+        }
+    }
+
+    Ok(is_empty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, Options};
+    use crate::test_support::touch;
+    use crate::{Tree, atomic, scheme};
+
+    #[test]
+    fn test_repair() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let base = temp_dir.path();
+        let tree = Tree::builder(base)
+            .with_scheme(scheme::hex::Hex::<3>::default())
+            .with_prefix_part_lengths([2, 2])
+            .with_extension("bin")
+            .build()?;
+
+        for name in [[0, 1, 2], [255, 0, 0]] {
+            assert!(tree.put(name, b"", atomic::Policy::Refuse)?);
+        }
+
+        touch(base.join("00/02/000102.bin"))?;
+        touch(base.join("000/01/000104.bin"))?;
+        touch(base.join("00/01/000105.txt"))?;
+        touch(base.join("00/01/.prefix-file-tree-staging-1-1"))?;
+        std::fs::create_dir_all(base.join("ff/ff"))?;
+
+        let lost_and_found = base.join(".prefix-file-tree-lost+found");
+
+        let expected_actions = vec![
+            Action::Quarantine {
+                from: base.join("00/02/000102.bin"),
+                to: lost_and_found.join("00/02/000102.bin"),
+            },
+            Action::Move {
+                from: base.join("000/01/000104.bin"),
+                to: base.join("00/01/000104.bin"),
+            },
+            Action::Quarantine {
+                from: base.join("00/01/000105.txt"),
+                to: lost_and_found.join("00/01/000105.txt"),
+            },
+            Action::RemoveFile(base.join("00/01/.prefix-file-tree-staging-1-1")),
+        ];
+
+        let planned = tree.repair(Options::default().with_dry_run())?;
+
+        assert_eq!(planned[0..4], expected_actions);
+
+        let mut planned_removals = planned[4..].to_vec();
+        planned_removals.sort_by_key(|action| format!("{action:?}"));
+
+        assert_eq!(
+            planned_removals,
+            vec![
+                Action::RemoveDirectory(base.join("00/02")),
+                Action::RemoveDirectory(base.join("000")),
+                Action::RemoveDirectory(base.join("000/01")),
+                Action::RemoveDirectory(base.join("ff/ff")),
+            ]
+        );
+
+        // Nothing has changed yet.
+        assert_eq!(tree.check()?.issue_count(), 6);
+
+        let performed = tree.repair(Options::default())?;
+
+        assert_eq!(performed, planned);
+
+        let report = tree.check()?;
+
+        assert!(report.is_consistent(), "{report:?}");
+        assert_eq!(report.entries, 3);
+        assert!(lost_and_found.join("00/02/000102.bin").is_file());
+        assert!(lost_and_found.join("00/01/000105.txt").is_file());
+        assert_eq!(tree.entries().collect::<Result<Vec<_>, _>>()?.len(), 3);
+
+        // Running it again does nothing.
+        assert_eq!(tree.repair(Options::default())?, vec![]);
+
+        Ok(())
+    }
+}
@@ -1,12 +1,14 @@
 use crate::scheme::{Case, Error, Scheme};
-use data_encoding::BASE32;
+use data_encoding::{BASE32, BASE32_NOPAD, BASE64URL_NOPAD, Encoding};
 use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::ffi::OsStr;
+use std::ops::Range;
 
 /// Fixed-length Base32 name encoding scheme.
 ///
-/// Note that padding is not handled, and that `N` must be a multiple of 5.
+/// Note that padding is not handled, and that `N` must be a multiple of 5. Names are written in
+/// uppercase unless the case is `Case::Lower`, and `Case::Any` accepts both cases when reading.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Base32<const N: usize> {
     pub case: Case,
@@ -34,33 +36,389 @@ impl<const N: usize> Scheme for Base32<N> {
     }
 
     fn name_to_string<'a>(&self, name: Self::NameRef<'a>) -> Cow<'a, str> {
-        BASE32.encode(&name).into()
+        encode(&BASE32, self.case, &name).into()
+    }
+
+    fn alphabet(&self) -> Option<Cow<'static, str>> {
+        Some(alphabet(self.case).into())
+    }
+
+    fn identifier(&self) -> Option<Cow<'static, str>> {
+        Some(format!("base32-{N}-{}", self.case.as_str()).into())
+    }
+
+    fn cmp_prefix_part(&self, a: &OsStr, b: &OsStr) -> Result<Ordering, Error> {
+        cmp_prefix_part(self.case, a, b)
+    }
+
+    fn name_from_file_stem(&self, file_stem: &OsStr) -> Result<Self::Name, Error> {
+        let () = Self::VALID;
+        let as_bytes = file_stem.as_encoded_bytes();
+
+        if as_bytes.len() == N / 5 * 8 {
+            let decoded = decode(&BASE32, self.case, as_bytes)?;
+
+            Ok(decoded.try_into().expect("Invalid decoded bytes length"))
+        } else {
+            Err(Error::InvalidLength(as_bytes.len()))
+        }
+    }
+}
+
+/// Variable-length Base32 name encoding scheme.
+///
+/// Names are written without padding unless `padding` is set (in which case padding is also
+/// required when reading). Case is handled as in `Base32`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AnyLengthBase32 {
+    pub case: Case,
+    pub padding: bool,
+    /// The allowed lengths of names in bytes, if restricted.
+    pub byte_lengths: Option<Range<usize>>,
+}
+
+impl AnyLengthBase32 {
+    #[must_use]
+    pub const fn new(case: Case) -> Self {
+        Self {
+            case,
+            padding: false,
+            byte_lengths: None,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_padding(self) -> Self {
+        Self {
+            padding: true,
+            ..self
+        }
+    }
+
+    /// Only allow names with lengths in bytes in the given range.
+    ///
+    /// The corresponding range of name string lengths is used as the tree's length constraint.
+    #[must_use]
+    pub const fn with_byte_lengths(self, byte_lengths: Range<usize>) -> Self {
+        Self {
+            byte_lengths: Some(byte_lengths),
+            ..self
+        }
+    }
+
+    const fn encoding(&self) -> Encoding {
+        if self.padding { BASE32 } else { BASE32_NOPAD }
+    }
+
+    /// The length of the name string for a name with the given length in bytes.
+    const fn encoded_length(&self, byte_length: usize) -> usize {
+        if self.padding {
+            byte_length.div_ceil(5) * 8
+        } else {
+            (byte_length * 8).div_ceil(5)
+        }
+    }
+}
+
+impl Scheme for AnyLengthBase32 {
+    type Name = Vec<u8>;
+    type NameRef<'a> = &'a [u8];
+
+    fn length_constraint(&self) -> Option<crate::constraint::Length> {
+        self.byte_lengths
+            .as_ref()
+            .filter(|byte_lengths| !byte_lengths.is_empty())
+            .map(|byte_lengths| {
+                crate::constraint::Length::Range(
+                    self.encoded_length(byte_lengths.start),
+                    self.encoded_length(byte_lengths.end - 1) + 1,
+                )
+            })
+    }
+
+    fn name_to_string<'a>(&self, name: Self::NameRef<'a>) -> Cow<'a, str> {
+        encode(&self.encoding(), self.case, name).into()
+    }
+
+    fn alphabet(&self) -> Option<Cow<'static, str>> {
+        Some(alphabet(self.case).into())
+    }
+
+    fn identifier(&self) -> Option<Cow<'static, str>> {
+        Some(if self.padding {
+            format!("base32-any-{}-padded", self.case.as_str()).into()
+        } else {
+            format!("base32-any-{}", self.case.as_str()).into()
+        })
     }
 
     fn cmp_prefix_part(&self, a: &OsStr, b: &OsStr) -> Result<Ordering, Error> {
-        let a_bytes = a.as_encoded_bytes();
-        let b_bytes = b.as_encoded_bytes();
-
-        for (a_byte, b_byte) in a_bytes.iter().zip(b_bytes.iter()) {
-            match Base32Char::try_from(*a_byte)?.cmp(&Base32Char::try_from(*b_byte)?) {
-                Ordering::Equal => {}
-                other => {
-                    return Ok(other);
-                }
+        cmp_prefix_part(self.case, a, b)
+    }
+
+    fn name_from_file_stem(&self, file_stem: &OsStr) -> Result<Self::Name, Error> {
+        let as_bytes = file_stem.as_encoded_bytes();
+        let decoded = decode(&self.encoding(), self.case, as_bytes)?;
+
+        if self
+            .byte_lengths
+            .as_ref()
+            .is_none_or(|byte_lengths| byte_lengths.contains(&decoded.len()))
+        {
+            Ok(decoded)
+        } else {
+            Err(Error::InvalidLength(as_bytes.len()))
+        }
+    }
+}
+
+/// Fixed-length unpadded Base64url name encoding scheme.
+///
+/// Names are ordered by their decoded bytes (not by the ASCII order of the name strings). Note
+/// that names that differ only in case will collide on case-insensitive file systems (see
+/// `Tree::case_collisions`).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Base64Url<const N: usize>;
+
+impl<const N: usize> Scheme for Base64Url<N> {
+    type Name = [u8; N];
+    type NameRef<'a> = [u8; N];
+
+    fn fixed_length() -> Option<usize> {
+        Some((N * 4).div_ceil(3))
+    }
+
+    fn name_to_string<'a>(&self, name: Self::NameRef<'a>) -> Cow<'a, str> {
+        BASE64URL_NOPAD.encode(&name).into()
+    }
+
+    fn alphabet(&self) -> Option<Cow<'static, str>> {
+        Some(BASE64URL_ALPHABET.into())
+    }
+
+    fn identifier(&self) -> Option<Cow<'static, str>> {
+        Some(format!("base64url-{N}").into())
+    }
+
+    fn cmp_prefix_part(&self, a: &OsStr, b: &OsStr) -> Result<Ordering, Error> {
+        cmp_base64url_prefix_part(a, b)
+    }
+
+    fn name_from_file_stem(&self, file_stem: &OsStr) -> Result<Self::Name, Error> {
+        let as_bytes = file_stem.as_encoded_bytes();
+
+        if Self::fixed_length() == Some(as_bytes.len()) {
+            let decoded = decode_base64url(as_bytes)?;
+
+            Ok(decoded.try_into().expect("Invalid decoded bytes length"))
+        } else {
+            Err(Error::InvalidLength(as_bytes.len()))
+        }
+    }
+}
+
+/// Variable-length unpadded Base64url name encoding scheme.
+///
+/// See `Base64Url` for details about ordering and case-insensitive file systems.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AnyLengthBase64Url {
+    /// The allowed lengths of names in bytes, if restricted.
+    pub byte_lengths: Option<Range<usize>>,
+}
+
+impl AnyLengthBase64Url {
+    /// Only allow names with lengths in bytes in the given range.
+    ///
+    /// The corresponding range of name string lengths is used as the tree's length constraint.
+    #[must_use]
+    pub const fn with_byte_lengths(self, byte_lengths: Range<usize>) -> Self {
+        Self {
+            byte_lengths: Some(byte_lengths),
+        }
+    }
+}
+
+impl Scheme for AnyLengthBase64Url {
+    type Name = Vec<u8>;
+    type NameRef<'a> = &'a [u8];
+
+    fn length_constraint(&self) -> Option<crate::constraint::Length> {
+        self.byte_lengths
+            .as_ref()
+            .filter(|byte_lengths| !byte_lengths.is_empty())
+            .map(|byte_lengths| {
+                crate::constraint::Length::Range(
+                    (byte_lengths.start * 4).div_ceil(3),
+                    ((byte_lengths.end - 1) * 4).div_ceil(3) + 1,
+                )
+            })
+    }
+
+    fn name_to_string<'a>(&self, name: Self::NameRef<'a>) -> Cow<'a, str> {
+        BASE64URL_NOPAD.encode(name).into()
+    }
+
+    fn alphabet(&self) -> Option<Cow<'static, str>> {
+        Some(BASE64URL_ALPHABET.into())
+    }
+
+    fn identifier(&self) -> Option<Cow<'static, str>> {
+        Some("base64url-any".into())
+    }
+
+    fn cmp_prefix_part(&self, a: &OsStr, b: &OsStr) -> Result<Ordering, Error> {
+        cmp_base64url_prefix_part(a, b)
+    }
+
+    fn name_from_file_stem(&self, file_stem: &OsStr) -> Result<Self::Name, Error> {
+        let as_bytes = file_stem.as_encoded_bytes();
+        let decoded = decode_base64url(as_bytes)?;
+
+        if self
+            .byte_lengths
+            .as_ref()
+            .is_none_or(|byte_lengths| byte_lengths.contains(&decoded.len()))
+        {
+            Ok(decoded)
+        } else {
+            Err(Error::InvalidLength(as_bytes.len()))
+        }
+    }
+}
+
+const BASE64URL_ALPHABET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn decode_base64url(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    BASE64URL_NOPAD.decode(bytes).map_err(|error| {
+        if error.kind == data_encoding::DecodeKind::Length {
+            Error::InvalidLength(bytes.len())
+        } else {
+            Error::InvalidByte(bytes[error.position])
+        }
+    })
+}
+
+/// The position of a byte in the Base64url alphabet.
+const fn base64url_value(value: u8) -> Result<u8, Error> {
+    match value {
+        b'A'..=b'Z' => Ok(value - b'A'),
+        b'a'..=b'z' => Ok(value - b'a' + 26),
+        b'0'..=b'9' => Ok(value - b'0' + 52),
+        b'-' => Ok(62),
+        b'_' => Ok(63),
+        _ => Err(Error::InvalidByte(value)),
+    }
+}
+
+/// Compare prefix parts by the alphabet's order, which is the order of the decoded bytes.
+fn cmp_base64url_prefix_part(a: &OsStr, b: &OsStr) -> Result<Ordering, Error> {
+    let a_bytes = a.as_encoded_bytes();
+    let b_bytes = b.as_encoded_bytes();
+
+    for (a_byte, b_byte) in a_bytes.iter().zip(b_bytes.iter()) {
+        match base64url_value(*a_byte)?.cmp(&base64url_value(*b_byte)?) {
+            Ordering::Equal => {}
+            other => {
+                return Ok(other);
             }
         }
+    }
+
+    Ok(a_bytes.len().cmp(&b_bytes.len()))
+}
+
+/// Fixed-length Crockford Base32 name encoding scheme.
+///
+/// Names are encoded as big-endian numbers, so any unused bits are at the start (as in ULIDs),
+/// and are written in uppercase unless the case is `Case::Lower`. Decoding is case-insensitive
+/// and accepts `I` and `L` for `1` and `O` for `0`, although only canonical name strings will be
+/// found by `Tree::open_file`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Crockford<const N: usize> {
+    pub case: Case,
+}
 
-        Ok(a_bytes.len().cmp(&b_bytes.len()))
+impl<const N: usize> Crockford<N> {
+    #[must_use]
+    pub const fn new(case: Case) -> Self {
+        Self { case }
+    }
+}
+
+impl<const N: usize> Scheme for Crockford<N> {
+    type Name = [u8; N];
+    type NameRef<'a> = [u8; N];
+
+    fn fixed_length() -> Option<usize> {
+        Some((N * 8).div_ceil(5))
+    }
+
+    fn name_to_string<'a>(&self, name: Self::NameRef<'a>) -> Cow<'a, str> {
+        let alphabet = crockford_alphabet(self.case).as_bytes();
+
+        encode_5_bit(&name, BitOrder::Leading, alphabet).into()
+    }
+
+    fn alphabet(&self) -> Option<Cow<'static, str>> {
+        Some(crockford_alphabet(self.case).into())
+    }
+
+    fn identifier(&self) -> Option<Cow<'static, str>> {
+        Some(format!("crockford-{N}-{}", self.case.as_str()).into())
+    }
+
+    fn cmp_prefix_part(&self, a: &OsStr, b: &OsStr) -> Result<Ordering, Error> {
+        cmp_5_bit_prefix_part(a, b, crockford_value)
     }
 
     fn name_from_file_stem(&self, file_stem: &OsStr) -> Result<Self::Name, Error> {
-        let () = Self::VALID;
         let as_bytes = file_stem.as_encoded_bytes();
 
-        if as_bytes.len() == N / 5 * 8 {
-            let decoded = BASE32
-                .decode(as_bytes)
-                .map_err(|error| Error::InvalidByte(as_bytes[error.position]))?;
+        if Self::fixed_length() == Some(as_bytes.len()) {
+            let decoded = decode_5_bit(as_bytes, BitOrder::Leading, crockford_value)?;
+
+            Ok(decoded.try_into().expect("Invalid decoded bytes length"))
+        } else {
+            Err(Error::InvalidLength(as_bytes.len()))
+        }
+    }
+}
+
+/// Fixed-length z-base-32 name encoding scheme.
+///
+/// Any unused bits are at the end, and name strings are always lowercase.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ZBase32<const N: usize>;
+
+impl<const N: usize> Scheme for ZBase32<N> {
+    type Name = [u8; N];
+    type NameRef<'a> = [u8; N];
+
+    fn fixed_length() -> Option<usize> {
+        Some((N * 8).div_ceil(5))
+    }
+
+    fn name_to_string<'a>(&self, name: Self::NameRef<'a>) -> Cow<'a, str> {
+        encode_5_bit(&name, BitOrder::Trailing, Z_BASE_32_ALPHABET.as_bytes()).into()
+    }
+
+    fn alphabet(&self) -> Option<Cow<'static, str>> {
+        Some(Z_BASE_32_ALPHABET.into())
+    }
+
+    fn identifier(&self) -> Option<Cow<'static, str>> {
+        Some(format!("zbase32-{N}").into())
+    }
+
+    fn cmp_prefix_part(&self, a: &OsStr, b: &OsStr) -> Result<Ordering, Error> {
+        cmp_5_bit_prefix_part(a, b, z_base_32_value)
+    }
+
+    fn name_from_file_stem(&self, file_stem: &OsStr) -> Result<Self::Name, Error> {
+        let as_bytes = file_stem.as_encoded_bytes();
+
+        if Self::fixed_length() == Some(as_bytes.len()) {
+            let decoded = decode_5_bit(as_bytes, BitOrder::Trailing, z_base_32_value)?;
 
             Ok(decoded.try_into().expect("Invalid decoded bytes length"))
         } else {
@@ -69,29 +427,238 @@ impl<const N: usize> Scheme for Base32<N> {
     }
 }
 
+const Z_BASE_32_ALPHABET: &str = "ybndrfg8ejkmcpqxot1uwisza345h769";
+
+const fn crockford_alphabet(case: Case) -> &'static str {
+    if matches!(case, Case::Lower) {
+        "0123456789abcdefghjkmnpqrstvwxyz"
+    } else {
+        // We use uppercase for the `Any` case.
+        "0123456789ABCDEFGHJKMNPQRSTVWXYZ"
+    }
+}
+
+const fn crockford_value(value: u8) -> Result<u8, Error> {
+    match value.to_ascii_uppercase() {
+        c @ b'0'..=b'9' => Ok(c - b'0'),
+        b'O' => Ok(0),
+        b'I' | b'L' => Ok(1),
+        c @ b'A'..=b'H' => Ok(c - b'A' + 10),
+        b'J' => Ok(18),
+        b'K' => Ok(19),
+        b'M' => Ok(20),
+        b'N' => Ok(21),
+        c @ b'P'..=b'T' => Ok(c - b'P' + 22),
+        c @ b'V'..=b'Z' => Ok(c - b'V' + 27),
+        _ => Err(Error::InvalidByte(value)),
+    }
+}
+
+fn z_base_32_value(value: u8) -> Result<u8, Error> {
+    Z_BASE_32_ALPHABET
+        .bytes()
+        .position(|c| c == value)
+        .and_then(|position| u8::try_from(position).ok())
+        .ok_or(Error::InvalidByte(value))
+}
+
+/// Where the unused bits go when the number of bits isn't a multiple of five.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum BitOrder {
+    Leading,
+    Trailing,
+}
+
+fn encode_5_bit(bytes: &[u8], bit_order: BitOrder, alphabet: &[u8]) -> String {
+    let length = (bytes.len() * 8).div_ceil(5);
+    let mut result = String::with_capacity(length);
+
+    // The accumulator never holds more than 12 bits.
+    let mut accumulator = 0u16;
+    let mut bit_count = match bit_order {
+        BitOrder::Leading => length * 5 - bytes.len() * 8,
+        BitOrder::Trailing => 0,
+    };
+
+    for byte in bytes {
+        accumulator = (accumulator << 8) | u16::from(*byte);
+        bit_count += 8;
+
+        while bit_count >= 5 {
+            bit_count -= 5;
+            result.push(char::from(
+                alphabet[usize::from(accumulator >> bit_count) & 31],
+            ));
+            accumulator &= (1 << bit_count) - 1;
+        }
+    }
+
+    if bit_count > 0 {
+        result.push(char::from(
+            alphabet[usize::from(accumulator << (5 - bit_count)) & 31],
+        ));
+    }
+
+    result
+}
+
+/// Decode a canonical 5-bit encoding (the unused bits must be zero).
+fn decode_5_bit<F: Fn(u8) -> Result<u8, Error>>(
+    encoded: &[u8],
+    bit_order: BitOrder,
+    value: F,
+) -> Result<Vec<u8>, Error> {
+    let length = encoded.len() * 5 / 8;
+    let unused_bit_count = encoded.len() * 5 - length * 8;
+
+    // Otherwise there would be a character that contains only unused bits.
+    if unused_bit_count >= 5 {
+        return Err(Error::InvalidLength(encoded.len()));
+    }
+
+    let mut result = Vec::with_capacity(length);
+    let mut accumulator = 0u16;
+    let mut bit_count = 0;
+
+    for (i, c) in encoded.iter().enumerate() {
+        let c_value = value(*c)?;
+
+        accumulator = (accumulator << 5) | u16::from(c_value);
+        bit_count += 5;
+
+        if i == 0 && bit_order == BitOrder::Leading {
+            bit_count -= unused_bit_count;
+
+            if accumulator >> bit_count != 0 {
+                return Err(Error::InvalidByte(*c));
+            }
+        }
+
+        if bit_count >= 8 {
+            bit_count -= 8;
+            result.push(u8::try_from(accumulator >> bit_count).expect("Invalid accumulator"));
+            accumulator &= (1 << bit_count) - 1;
+        }
+    }
+
+    if accumulator == 0 {
+        Ok(result)
+    } else {
+        // Only the trailing unused bits can be left.
+        Err(Error::InvalidByte(encoded[encoded.len() - 1]))
+    }
+}
+
+/// Compare prefix parts by their decoded values.
+fn cmp_5_bit_prefix_part<F: Fn(u8) -> Result<u8, Error>>(
+    a: &OsStr,
+    b: &OsStr,
+    value: F,
+) -> Result<Ordering, Error> {
+    let a_bytes = a.as_encoded_bytes();
+    let b_bytes = b.as_encoded_bytes();
+
+    for (a_byte, b_byte) in a_bytes.iter().zip(b_bytes.iter()) {
+        match value(*a_byte)?.cmp(&value(*b_byte)?) {
+            Ordering::Equal => {}
+            other => {
+                return Ok(other);
+            }
+        }
+    }
+
+    Ok(a_bytes.len().cmp(&b_bytes.len()))
+}
+
+const fn alphabet(case: Case) -> &'static str {
+    if matches!(case, Case::Lower) {
+        "abcdefghijklmnopqrstuvwxyz234567"
+    } else {
+        // We use uppercase for the `Any` case.
+        "ABCDEFGHIJKLMNOPQRSTUVWXYZ234567"
+    }
+}
+
+fn encode(encoding: &Encoding, case: Case, bytes: &[u8]) -> String {
+    let mut encoded = encoding.encode(bytes);
+
+    if case == Case::Lower {
+        encoded.make_ascii_lowercase();
+    }
+
+    encoded
+}
+
+fn decode(encoding: &Encoding, case: Case, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    let normalized = bytes
+        .iter()
+        .map(|byte| normalize_byte(case, *byte))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    encoding.decode(&normalized).map_err(|error| {
+        if error.kind == data_encoding::DecodeKind::Length {
+            Error::InvalidLength(bytes.len())
+        } else {
+            Error::InvalidByte(bytes[error.position])
+        }
+    })
+}
+
+/// Compare prefix parts by the alphabet's order, with padding first.
+fn cmp_prefix_part(case: Case, a: &OsStr, b: &OsStr) -> Result<Ordering, Error> {
+    let a_bytes = a.as_encoded_bytes();
+    let b_bytes = b.as_encoded_bytes();
+
+    for (a_byte, b_byte) in a_bytes.iter().zip(b_bytes.iter()) {
+        match Base32Char::new(case, *a_byte)?.cmp(&Base32Char::new(case, *b_byte)?) {
+            Ordering::Equal => {}
+            other => {
+                return Ok(other);
+            }
+        }
+    }
+
+    Ok(a_bytes.len().cmp(&b_bytes.len()))
+}
+
 #[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
 enum Base32Char {
+    Padding,
     Alphabetic(u8),
     Numeric(u8),
 }
 
-impl TryFrom<u8> for Base32Char {
-    type Error = Error;
+impl Base32Char {
+    fn new(case: Case, value: u8) -> Result<Self, Error> {
+        let normalized = normalize_byte(case, value)?;
 
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        if value.is_ascii_uppercase() {
-            Ok(Self::Alphabetic(value))
-        } else if (b'2'..=b'7').contains(&value) {
-            Ok(Self::Numeric(value))
+        if normalized == b'=' {
+            Ok(Self::Padding)
+        } else if normalized.is_ascii_uppercase() {
+            Ok(Self::Alphabetic(normalized))
+        } else if (b'2'..=b'7').contains(&normalized) {
+            Ok(Self::Numeric(normalized))
         } else {
             Err(Error::InvalidByte(value))
         }
     }
 }
 
+/// Convert a byte of an encoded name to uppercase, rejecting letters in the wrong case.
+const fn normalize_byte(case: Case, value: u8) -> Result<u8, Error> {
+    match case {
+        Case::Lower if value.is_ascii_uppercase() => Err(Error::InvalidByte(value)),
+        Case::Upper if value.is_ascii_lowercase() => Err(Error::InvalidByte(value)),
+        _ => Ok(value.to_ascii_uppercase()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::Tree;
+    use crate::scheme::{Case, Error, Scheme};
+    use std::cmp::Ordering;
+    use std::ffi::OsStr;
     use std::io::Write;
 
     #[test]
@@ -133,7 +700,7 @@ mod tests {
             entries[0]
                 .path
                 .to_string_lossy()
-                .ends_with("/MFR/GG/MFRGGZC7MFRGGZC7MFRGGZC7MFRGGZC7")
+                .ends_with("/mfr/gg/mfrggzc7mfrggzc7mfrggzc7mfrggzc7")
         );
 
         assert_eq!(
@@ -146,4 +713,344 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_base32_case() {
+        let name = *b"abcd_";
+
+        let lower = super::Base32::<5>::new(Case::Lower);
+        let upper = super::Base32::<5>::new(Case::Upper);
+        let any = super::Base32::<5>::new(Case::Any);
+
+        assert_eq!(lower.name_to_string(name), "mfrggzc7");
+        assert_eq!(upper.name_to_string(name), "MFRGGZC7");
+        assert_eq!(any.name_to_string(name), "MFRGGZC7");
+
+        assert_eq!(lower.name_from_file_stem(OsStr::new("mfrggzc7")), Ok(name));
+        assert_eq!(
+            lower.name_from_file_stem(OsStr::new("mfrgGzc7")),
+            Err(Error::InvalidByte(b'G'))
+        );
+        assert_eq!(upper.name_from_file_stem(OsStr::new("MFRGGZC7")), Ok(name));
+        assert_eq!(
+            upper.name_from_file_stem(OsStr::new("MFRgGZC7")),
+            Err(Error::InvalidByte(b'g'))
+        );
+        assert_eq!(any.name_from_file_stem(OsStr::new("mfrggzc7")), Ok(name));
+        assert_eq!(any.name_from_file_stem(OsStr::new("MFRgGZC7")), Ok(name));
+        assert_eq!(
+            any.name_from_file_stem(OsStr::new("MFRGGZC1")),
+            Err(Error::InvalidByte(b'1'))
+        );
+    }
+
+    #[test]
+    fn test_base32_case_ordering() {
+        let lower = super::Base32::<5>::new(Case::Lower);
+        let any = super::Base32::<5>::new(Case::Any);
+
+        // Letters sort before digits.
+        assert_eq!(
+            lower.cmp_prefix_part(OsStr::new("z"), OsStr::new("2")),
+            Ok(std::cmp::Ordering::Less)
+        );
+        assert_eq!(
+            lower.cmp_prefix_part(OsStr::new("Z"), OsStr::new("2")),
+            Err(Error::InvalidByte(b'Z'))
+        );
+        assert_eq!(
+            any.cmp_prefix_part(OsStr::new("ab"), OsStr::new("AB")),
+            Ok(std::cmp::Ordering::Equal)
+        );
+        assert_eq!(
+            any.cmp_prefix_part(OsStr::new("aC"), OsStr::new("Ab")),
+            Ok(std::cmp::Ordering::Greater)
+        );
+    }
+
+    #[test]
+    fn test_any_length_base32() -> Result<(), Box<dyn std::error::Error>> {
+        let scheme = super::AnyLengthBase32::new(Case::Upper);
+        let padded = super::AnyLengthBase32::new(Case::Lower).with_padding();
+
+        assert_eq!(scheme.name_to_string(b"a"), "ME");
+        assert_eq!(padded.name_to_string(b"a"), "me======");
+        assert_eq!(scheme.name_from_file_stem(OsStr::new("MFRA"))?, b"ab");
+        assert_eq!(padded.name_from_file_stem(OsStr::new("mfra===="))?, b"ab");
+        assert_eq!(
+            scheme.name_from_file_stem(OsStr::new("MFR")),
+            Err(Error::InvalidLength(3))
+        );
+        assert_eq!(
+            padded.name_from_file_stem(OsStr::new("mfra")),
+            Err(Error::InvalidLength(4))
+        );
+
+        // Non-zero trailing bits would give a second name string for the same name.
+        assert!(scheme.name_from_file_stem(OsStr::new("MF")).is_err());
+
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(padded.clone().with_byte_lengths(1..11))
+            .with_prefix_part_lengths([2])
+            .build()?;
+
+        let mut names = vec![
+            b"".to_vec(),
+            b"a".to_vec(),
+            b"ab".to_vec(),
+            b"abcdefghij".to_vec(),
+            vec![0],
+            vec![255, 255, 255],
+            vec![97, 0],
+            vec![97, 255],
+        ];
+
+        assert!(tree.create_file(&[]).is_err());
+        assert_eq!(
+            padded
+                .clone()
+                .with_byte_lengths(1..11)
+                .name_from_file_stem(OsStr::new(padded.name_to_string(&[0; 11]).as_ref())),
+            Err(Error::InvalidLength(24))
+        );
+
+        for name in &names[1..] {
+            drop(tree.create_file(name)?.expect("Failed to create file"));
+        }
+
+        names.remove(0);
+        names.sort();
+
+        let entries = tree
+            .entries()
+            .map(|entry| entry.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(entries, names);
+        assert!(tree.check()?.is_consistent());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_any_length_base32_length_constraint() {
+        assert_eq!(
+            super::AnyLengthBase32::new(Case::Upper).length_constraint(),
+            None
+        );
+        assert_eq!(
+            super::AnyLengthBase32::new(Case::Upper)
+                .with_byte_lengths(1..11)
+                .length_constraint(),
+            Some(crate::constraint::Length::Range(2, 17))
+        );
+        assert_eq!(
+            super::AnyLengthBase32::new(Case::Upper)
+                .with_padding()
+                .with_byte_lengths(1..11)
+                .length_constraint(),
+            Some(crate::constraint::Length::Range(8, 17))
+        );
+    }
+
+    #[test]
+    fn test_base64url() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(super::Base64Url::<4>)
+            .with_prefix_part_lengths([1, 1])
+            .build()?;
+
+        let mut names = (0..200u8)
+            .map(|i| [i.wrapping_mul(151), i, 255 - i, 7])
+            .collect::<Vec<_>>();
+
+        for name in &names {
+            drop(tree.create_file(*name)?.expect("Failed to create file"));
+        }
+
+        names.sort_unstable();
+
+        let entries = tree
+            .entries()
+            .map(|entry| entry.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(entries, names);
+        assert_eq!(
+            tree.path([251, 255, 191, 0])?,
+            temp_dir.path().join("-/_/-_-_AA")
+        );
+        assert!(tree.check()?.is_consistent());
+
+        let scheme = super::Base64Url::<4>;
+
+        assert_eq!(
+            scheme.name_from_file_stem(OsStr::new("-_-_AA"))?,
+            [251, 255, 191, 0]
+        );
+        assert_eq!(
+            scheme.name_from_file_stem(OsStr::new("-_-_A+")),
+            Err(Error::InvalidByte(b'+'))
+        );
+        assert_eq!(
+            scheme.name_from_file_stem(OsStr::new("-_-_A")),
+            Err(Error::InvalidLength(5))
+        );
+        assert_eq!(
+            scheme.cmp_prefix_part(OsStr::new("a"), OsStr::new("Z")),
+            Ok(Ordering::Greater)
+        );
+        assert_eq!(
+            scheme.cmp_prefix_part(OsStr::new("_"), OsStr::new("a")),
+            Ok(Ordering::Greater)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_any_length_base64url() -> Result<(), Box<dyn std::error::Error>> {
+        let scheme = super::AnyLengthBase64Url::default().with_byte_lengths(1..7);
+
+        assert_eq!(
+            scheme.length_constraint(),
+            Some(crate::constraint::Length::Range(2, 9))
+        );
+
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme)
+            .with_prefix_part_lengths([2])
+            .build()?;
+
+        let mut names = vec![
+            vec![0],
+            vec![255],
+            vec![0, 0, 0, 0, 0, 0],
+            vec![62, 1],
+            vec![255, 255, 255, 255],
+        ];
+
+        for name in &names {
+            drop(tree.create_file(name)?.expect("Failed to create file"));
+        }
+
+        names.sort();
+
+        let entries = tree
+            .entries()
+            .map(|entry| entry.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(entries, names);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_crockford() -> Result<(), Box<dyn std::error::Error>> {
+        let scheme = super::Crockford::<16>::new(Case::Upper);
+
+        // A ULID, with its 48-bit timestamp first.
+        let ulid = "01ARZ3NDEKTSV4RRFFQ69G5FAV";
+        let name = scheme.name_from_file_stem(OsStr::new(ulid))?;
+
+        assert_eq!(name[0..6], [1, 86, 62, 58, 181, 211]);
+        assert_eq!(scheme.name_to_string(name), ulid);
+        assert_eq!(
+            super::Crockford::<16>::new(Case::Lower).name_to_string(name),
+            ulid.to_ascii_lowercase()
+        );
+
+        // Decoding is case-insensitive and lenient.
+        assert_eq!(
+            scheme.name_from_file_stem(OsStr::new("oIarz3ndektsv4rrffq69g5fav"))?,
+            name
+        );
+        assert_eq!(
+            scheme.name_from_file_stem(OsStr::new("01ARZ3NDEKTSV4RRFFQ69G5FAU")),
+            Err(Error::InvalidByte(b'U'))
+        );
+
+        // The first character can only encode three bits.
+        assert_eq!(
+            scheme.name_from_file_stem(OsStr::new("81ARZ3NDEKTSV4RRFFQ69G5FAV")),
+            Err(Error::InvalidByte(b'8'))
+        );
+
+        assert_eq!(
+            scheme.cmp_prefix_part(OsStr::new("l"), OsStr::new("0")),
+            Ok(Ordering::Greater)
+        );
+        assert_eq!(
+            scheme.cmp_prefix_part(OsStr::new("Z"), OsStr::new("a")),
+            Ok(Ordering::Greater)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_z_base_32() -> Result<(), Box<dyn std::error::Error>> {
+        let scheme = super::ZBase32::<2>;
+
+        assert_eq!(scheme.name_to_string([0xf0, 0xbf]), "6n9o");
+        assert_eq!(scheme.name_to_string([0xd4, 0x7a]), "4t7y");
+        assert_eq!(
+            scheme.name_from_file_stem(OsStr::new("6n9o"))?,
+            [0xf0, 0xbf]
+        );
+
+        // The last character only encodes one bit.
+        assert_eq!(
+            scheme.name_from_file_stem(OsStr::new("6n9b")),
+            Err(Error::InvalidByte(b'b'))
+        );
+        assert_eq!(
+            scheme.name_from_file_stem(OsStr::new("6N9o")),
+            Err(Error::InvalidByte(b'N'))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_5_bit_ordering() -> Result<(), Box<dyn std::error::Error>> {
+        let mut names = (0..300u16)
+            .map(|i| (i.wrapping_mul(2_477) ^ 0x5a5a).to_be_bytes())
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names.dedup();
+
+        let temp_dir = tempfile::tempdir()?;
+        let crockford = Tree::builder(temp_dir.path().join("crockford"))
+            .with_scheme(super::Crockford::<2>::new(Case::Lower))
+            .with_prefix_part_lengths([1, 1])
+            .build()?;
+        let z_base_32 = Tree::builder(temp_dir.path().join("z-base-32"))
+            .with_scheme(super::ZBase32::<2>)
+            .with_prefix_part_lengths([1, 1])
+            .build()?;
+
+        for name in &names {
+            drop(crockford.create_file(*name)?);
+            drop(z_base_32.create_file(*name)?);
+        }
+
+        let crockford_names = crockford
+            .entries()
+            .map(|entry| entry.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let z_base_32_names = z_base_32
+            .entries()
+            .map(|entry| entry.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(crockford_names, names);
+        assert_eq!(z_base_32_names, names);
+
+        Ok(())
+    }
 }
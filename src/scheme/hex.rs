@@ -27,6 +27,14 @@ impl<const N: usize> Scheme for Hex<N> {
         bytes_to_string(self.case, name).into()
     }
 
+    fn alphabet(&self) -> Option<Cow<'static, str>> {
+        Some(alphabet(self.case).into())
+    }
+
+    fn identifier(&self) -> Option<Cow<'static, str>> {
+        Some(format!("hex-{N}-{}", self.case.as_str()).into())
+    }
+
     fn name_from_file_stem(&self, file_stem: &OsStr) -> Result<Self::Name, Error> {
         let as_str = file_stem.to_str().ok_or(Error::NonUtf8)?;
 
@@ -71,6 +79,14 @@ impl Scheme for AnyLengthHex {
         bytes_to_string(self.case, name).into()
     }
 
+    fn alphabet(&self) -> Option<Cow<'static, str>> {
+        Some(alphabet(self.case).into())
+    }
+
+    fn identifier(&self) -> Option<Cow<'static, str>> {
+        Some(format!("hex-any-{}", self.case.as_str()).into())
+    }
+
     fn name_from_file_stem(&self, file_stem: &OsStr) -> Result<Self::Name, Error> {
         let as_str = file_stem.to_str().ok_or(Error::NonUtf8)?;
 
@@ -94,6 +110,15 @@ impl Scheme for AnyLengthHex {
     }
 }
 
+const fn alphabet(case: Case) -> &'static str {
+    if matches!(case, Case::Upper) {
+        "0123456789ABCDEF"
+    } else {
+        // We use lowercase for the `Any` case.
+        "0123456789abcdef"
+    }
+}
+
 fn first_invalid_byte(case: Case, value: &str) -> Option<u8> {
     value
         .as_bytes()
@@ -0,0 +1,367 @@
+use crate::scheme::{Case, Error, Scheme};
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::marker::PhantomData;
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for u32 {}
+    impl Sealed for u64 {}
+    impl Sealed for u128 {}
+}
+
+/// An unsigned integer type that can be used as a name.
+pub trait Integer: Copy + sealed::Sealed {
+    const NAME: &'static str;
+    /// The number of decimal digits in the largest value.
+    const DECIMAL_DIGITS: usize;
+    /// The number of hexadecimal digits in the largest value.
+    const HEX_DIGITS: usize;
+
+    fn to_u128(self) -> u128;
+    fn from_u128(value: u128) -> Option<Self>;
+}
+
+macro_rules! integer {
+    ($t:ty, $decimal_digits:expr) => {
+        impl Integer for $t {
+            const NAME: &'static str = stringify!($t);
+            const DECIMAL_DIGITS: usize = $decimal_digits;
+            const HEX_DIGITS: usize = <$t>::BITS as usize / 4;
+
+            fn to_u128(self) -> u128 {
+                self.into()
+            }
+
+            fn from_u128(value: u128) -> Option<Self> {
+                value.try_into().ok()
+            }
+        }
+    };
+}
+
+integer!(u32, 10);
+integer!(u64, 20);
+integer!(u128, 39);
+
+/// Integer names written as zero-padded decimal numbers of a fixed width.
+///
+/// The default width is enough for any value of the type. Values that need more digits than the
+/// width are rejected as invalid names, since the width is used as the tree's length constraint.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Decimal<T> {
+    width: usize,
+    _integer: PhantomData<T>,
+}
+
+impl<T: Integer> Default for Decimal<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Integer> Decimal<T> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            width: T::DECIMAL_DIGITS,
+            _integer: PhantomData,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_width(self, width: usize) -> Self {
+        Self { width, ..self }
+    }
+
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+}
+
+impl<T: Integer> Scheme for Decimal<T> {
+    type Name = T;
+    type NameRef<'a> = T;
+
+    fn length_constraint(&self) -> Option<crate::constraint::Length> {
+        Some(self.width.into())
+    }
+
+    fn name_to_string<'a>(&self, name: Self::NameRef<'a>) -> Cow<'a, str> {
+        format!("{:0width$}", name.to_u128(), width = self.width).into()
+    }
+
+    fn alphabet(&self) -> Option<Cow<'static, str>> {
+        Some("0123456789".into())
+    }
+
+    fn identifier(&self) -> Option<Cow<'static, str>> {
+        Some(format!("decimal-{}-{}", T::NAME, self.width).into())
+    }
+
+    fn name_from_file_stem(&self, file_stem: &OsStr) -> Result<Self::Name, Error> {
+        let as_bytes = file_stem.as_encoded_bytes();
+
+        match as_bytes.iter().find(|byte| !byte.is_ascii_digit()) {
+            Some(invalid_byte) => Err(Error::InvalidByte(*invalid_byte)),
+            None if as_bytes.len() == self.width => as_bytes
+                .iter()
+                .try_fold(0u128, |value, byte| {
+                    value
+                        .checked_mul(10)
+                        .and_then(|value| value.checked_add(u128::from(byte - b'0')))
+                })
+                .and_then(T::from_u128)
+                .ok_or(Error::OutOfRange),
+            None => Err(Error::InvalidLength(as_bytes.len())),
+        }
+    }
+}
+
+/// Integer names written as zero-padded hexadecimal numbers of a fixed width.
+///
+/// The default width is enough for any value of the type. Values that need more digits than the
+/// width are rejected as invalid names, since the width is used as the tree's length constraint.
+/// Names are written in lowercase unless the case is `Case::Upper`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct HexInt<T> {
+    pub case: Case,
+    width: usize,
+    _integer: PhantomData<T>,
+}
+
+impl<T: Integer> Default for HexInt<T> {
+    fn default() -> Self {
+        Self::new(Case::default())
+    }
+}
+
+impl<T: Integer> HexInt<T> {
+    #[must_use]
+    pub const fn new(case: Case) -> Self {
+        Self {
+            case,
+            width: T::HEX_DIGITS,
+            _integer: PhantomData,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_width(self, width: usize) -> Self {
+        Self { width, ..self }
+    }
+
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+}
+
+impl<T: Integer> Scheme for HexInt<T> {
+    type Name = T;
+    type NameRef<'a> = T;
+
+    fn length_constraint(&self) -> Option<crate::constraint::Length> {
+        Some(self.width.into())
+    }
+
+    fn name_to_string<'a>(&self, name: Self::NameRef<'a>) -> Cow<'a, str> {
+        if self.case == Case::Upper {
+            format!("{:0width$X}", name.to_u128(), width = self.width).into()
+        } else {
+            format!("{:0width$x}", name.to_u128(), width = self.width).into()
+        }
+    }
+
+    fn alphabet(&self) -> Option<Cow<'static, str>> {
+        Some(if self.case == Case::Upper {
+            "0123456789ABCDEF".into()
+        } else {
+            "0123456789abcdef".into()
+        })
+    }
+
+    fn identifier(&self) -> Option<Cow<'static, str>> {
+        Some(format!("hexint-{}-{}-{}", T::NAME, self.width, self.case.as_str()).into())
+    }
+
+    fn name_from_file_stem(&self, file_stem: &OsStr) -> Result<Self::Name, Error> {
+        let as_bytes = file_stem.as_encoded_bytes();
+        let is_valid = |byte: &u8| {
+            byte.is_ascii_digit()
+                || match self.case {
+                    Case::Lower => (b'a'..=b'f').contains(byte),
+                    Case::Upper => (b'A'..=b'F').contains(byte),
+                    Case::Any => byte.is_ascii_hexdigit(),
+                }
+        };
+
+        match as_bytes.iter().find(|byte| !is_valid(byte)) {
+            Some(invalid_byte) => Err(Error::InvalidByte(*invalid_byte)),
+            None if as_bytes.len() == self.width => as_bytes
+                .iter()
+                .try_fold(0u128, |value, byte| {
+                    let digit = char::from(*byte).to_digit(16)?;
+
+                    value
+                        .checked_mul(16)
+                        .and_then(|value| value.checked_add(u128::from(digit)))
+                })
+                .and_then(T::from_u128)
+                .ok_or(Error::OutOfRange),
+            None => Err(Error::InvalidLength(as_bytes.len())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Decimal, HexInt};
+    use crate::Tree;
+    use crate::scheme::{Case, Error, Scheme};
+    use std::ffi::OsStr;
+
+    #[test]
+    fn test_decimal() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(Decimal::<u64>::new().with_width(10))
+            .with_prefix_part_lengths([2, 2])
+            .build()?;
+
+        drop(tree.create_file(42)?.expect("Failed to create file"));
+
+        assert!(temp_dir.path().join("00/00/0000000042").is_file());
+
+        let mut names = vec![42, 7, 1_000, 999, 9_999_999_999, 100_000_000];
+
+        for name in &names[1..] {
+            drop(tree.create_file(*name)?.expect("Failed to create file"));
+        }
+
+        names.sort_unstable();
+
+        let entries = tree
+            .entries()
+            .map(|entry| entry.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(entries, names);
+        assert_eq!(
+            tree.entries_range(999..100_000_000)
+                .map(|entry| entry.map(|entry| entry.name))
+                .collect::<Result<Vec<_>, _>>()?,
+            vec![999, 1_000]
+        );
+        assert!(tree.check()?.is_consistent());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejects_values_wider_than_width() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(Decimal::<u64>::new().with_width(10))
+            .with_prefix_part_lengths([2, 2])
+            .build()?;
+
+        assert!(matches!(
+            tree.create_file(12_345_678_901),
+            Err(crate::Error::InvalidName(name)) if name == "12345678901"
+        ));
+        assert_eq!(std::fs::read_dir(temp_dir.path())?.count(), 0);
+
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(HexInt::<u64>::default().with_width(4))
+            .with_prefix_part_lengths([2])
+            .build()?;
+
+        assert_eq!(tree.path(0x1_0000), Err("10000".to_string()));
+        assert!(tree.path(0xffff).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decimal_name_from_file_stem() {
+        let scheme = Decimal::<u32>::default();
+
+        assert_eq!(scheme.name_to_string(u32::MAX), "4294967295");
+        assert_eq!(scheme.name_to_string(42), "0000000042");
+        assert_eq!(
+            scheme.name_from_file_stem(OsStr::new("4294967295")),
+            Ok(u32::MAX)
+        );
+        assert_eq!(
+            scheme.name_from_file_stem(OsStr::new("4294967296")),
+            Err(Error::OutOfRange)
+        );
+        assert_eq!(
+            scheme.name_from_file_stem(OsStr::new("+294967295")),
+            Err(Error::InvalidByte(b'+'))
+        );
+        assert_eq!(
+            scheme.name_from_file_stem(OsStr::new("42")),
+            Err(Error::InvalidLength(2))
+        );
+        assert_eq!(
+            Decimal::<u128>::default().name_to_string(u128::MAX).len(),
+            39
+        );
+    }
+
+    #[test]
+    fn test_hex_int() -> Result<(), Box<dyn std::error::Error>> {
+        let scheme = HexInt::<u32>::default();
+
+        assert_eq!(scheme.name_to_string(0xbeef), "0000beef");
+        assert_eq!(
+            HexInt::<u32>::new(Case::Upper).name_to_string(0xbeef),
+            "0000BEEF"
+        );
+        assert_eq!(
+            scheme.name_from_file_stem(OsStr::new("0000beef")),
+            Ok(0xbeef)
+        );
+        assert_eq!(
+            scheme.name_from_file_stem(OsStr::new("0000BEEF")),
+            Err(Error::InvalidByte(b'B'))
+        );
+        assert_eq!(
+            HexInt::<u32>::new(Case::Any).name_from_file_stem(OsStr::new("0000BEef")),
+            Ok(0xbeef)
+        );
+        assert_eq!(
+            HexInt::<u128>::default().name_to_string(u128::MAX).len(),
+            32
+        );
+
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(HexInt::<u64>::default())
+            .with_prefix_part_lengths([2])
+            .build()?;
+
+        let mut names = (0..100u64)
+            .map(|i| i.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+            .collect::<Vec<_>>();
+
+        for name in &names {
+            drop(tree.create_file(*name)?.expect("Failed to create file"));
+        }
+
+        names.sort_unstable();
+
+        let entries = tree
+            .entries()
+            .map(|entry| entry.map(|entry| entry.name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(entries, names);
+
+        Ok(())
+    }
+}
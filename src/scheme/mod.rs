@@ -5,6 +5,9 @@ use std::ffi::{OsStr, OsString};
 #[cfg(feature = "data-encoding")]
 pub mod encoding;
 pub mod hex;
+mod integer;
+
+pub use integer::{Decimal, HexInt, Integer};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, thiserror::Error)]
 pub enum Error {
@@ -14,6 +17,10 @@ pub enum Error {
     InvalidByte(u8),
     #[error("Invalid length")]
     InvalidLength(usize),
+    #[error("Invalid path component")]
+    InvalidPathComponent,
+    #[error("Value out of range")]
+    OutOfRange,
 }
 
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
@@ -24,6 +31,61 @@ pub enum Case {
     Any,
 }
 
+impl Case {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Lower => "lower",
+            Self::Upper => "upper",
+            Self::Any => "any",
+        }
+    }
+}
+
+/// The unit in which name lengths and prefix part lengths are measured.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PrefixUnit {
+    /// Bytes of the name string (splits must still fall on character boundaries).
+    #[default]
+    Byte,
+    /// Unicode code points (`char`s) of the name string.
+    Char,
+}
+
+impl PrefixUnit {
+    /// Return the length of the given value in this unit.
+    ///
+    /// The result will be empty if the unit is `Char` and the value is not valid UTF-8.
+    #[must_use]
+    pub fn length(self, value: &OsStr) -> Option<usize> {
+        match self {
+            Self::Byte => Some(value.len()),
+            Self::Char => value.to_str().map(|value| value.chars().count()),
+        }
+    }
+
+    /// Split the given string after the given number of units.
+    ///
+    /// The result will be empty if the string is too short, or if the split would not fall on a
+    /// character boundary.
+    #[must_use]
+    pub fn split_at(self, value: &str, length: usize) -> Option<(&str, &str)> {
+        match self {
+            Self::Byte => value.split_at_checked(length),
+            Self::Char => {
+                let index = if length == 0 {
+                    0
+                } else {
+                    let (index, c) = value.char_indices().nth(length - 1)?;
+                    index + c.len_utf8()
+                };
+
+                Some(value.split_at(index))
+            }
+        }
+    }
+}
+
 pub trait Scheme {
     type Name;
     type NameRef<'a>;
@@ -33,12 +95,54 @@ pub trait Scheme {
         None
     }
 
+    /// The length constraint on name strings implied by the scheme, if any.
+    fn length_constraint(&self) -> Option<crate::constraint::Length> {
+        Self::fixed_length().map(Into::into)
+    }
+
+    /// The unit used to measure name lengths and split names into prefix parts.
+    fn prefix_unit(&self) -> PrefixUnit {
+        PrefixUnit::Byte
+    }
+
     fn name_to_string<'a>(&self, name: Self::NameRef<'a>) -> Cow<'a, str>;
     fn name_from_file_stem(&self, file_stem: &OsStr) -> Result<Self::Name, Error>;
 
     fn cmp_prefix_part(&self, a: &OsStr, b: &OsStr) -> Result<Ordering, Error> {
         Ok(a.cmp(b))
     }
+
+    /// The characters that can appear in name strings, in the scheme's order, if known.
+    ///
+    /// This is used to divide the space of names into shards.
+    fn alphabet(&self) -> Option<Cow<'static, str>> {
+        None
+    }
+
+    /// A string that identifies the scheme and its configuration, if it has one.
+    ///
+    /// This is recorded in manifests, so that a tree isn't opened with a different scheme.
+    fn identifier(&self) -> Option<Cow<'static, str>> {
+        None
+    }
+}
+
+/// Check that a name string (or part of one) can be used as a single path component.
+///
+/// This rejects empty strings, `.` and `..`, and strings containing path separators or NUL bytes,
+/// since any of these could resolve to a location outside of the tree.
+pub fn validate_path_component(value: &str) -> Result<(), Error> {
+    if value.is_empty()
+        || value == "."
+        || value == ".."
+        || value
+            .chars()
+            .any(|c| c == '\0' || std::path::is_separator(c))
+    {
+        Err(Error::InvalidPathComponent)
+    } else {
+        Ok(())
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -48,11 +152,17 @@ impl Scheme for Identity {
     type Name = OsString;
     type NameRef<'a> = &'a OsStr;
 
+    fn identifier(&self) -> Option<Cow<'static, str>> {
+        Some("identity".into())
+    }
+
     fn name_to_string<'a>(&self, name: Self::NameRef<'a>) -> Cow<'a, str> {
         name.to_string_lossy()
     }
 
     fn name_from_file_stem(&self, file_stem: &OsStr) -> Result<Self::Name, Error> {
+        validate_path_component(&file_stem.to_string_lossy())?;
+
         Ok(file_stem.to_os_string())
     }
 }
@@ -64,14 +174,63 @@ impl Scheme for Utf8 {
     type Name = String;
     type NameRef<'a> = &'a str;
 
+    fn identifier(&self) -> Option<Cow<'static, str>> {
+        Some("utf8".into())
+    }
+
+    fn prefix_unit(&self) -> PrefixUnit {
+        PrefixUnit::Char
+    }
+
     fn name_to_string<'a>(&self, name: Self::NameRef<'a>) -> Cow<'a, str> {
         name.into()
     }
 
     fn name_from_file_stem(&self, file_stem: &OsStr) -> Result<Self::Name, Error> {
-        file_stem
-            .to_str()
-            .map(std::string::ToString::to_string)
-            .ok_or(Error::NonUtf8)
+        let file_stem = file_stem.to_str().ok_or(Error::NonUtf8)?;
+
+        validate_path_component(file_stem)?;
+
+        Ok(file_stem.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, PrefixUnit, validate_path_component};
+    use std::ffi::OsStr;
+
+    #[test]
+    fn test_prefix_unit_split_at() {
+        assert_eq!(PrefixUnit::Byte.split_at("abc", 1), Some(("a", "bc")));
+        assert_eq!(PrefixUnit::Byte.split_at("abc", 4), None);
+        assert_eq!(PrefixUnit::Byte.split_at("éclair", 1), None);
+        assert_eq!(PrefixUnit::Byte.split_at("éclair", 2), Some(("é", "clair")));
+
+        assert_eq!(PrefixUnit::Char.split_at("éclair", 0), Some(("", "éclair")));
+        assert_eq!(PrefixUnit::Char.split_at("éclair", 1), Some(("é", "clair")));
+        assert_eq!(PrefixUnit::Char.split_at("éé", 2), Some(("éé", "")));
+        assert_eq!(PrefixUnit::Char.split_at("éé", 3), None);
+    }
+
+    #[test]
+    fn test_prefix_unit_length() {
+        assert_eq!(PrefixUnit::Byte.length(OsStr::new("éclair")), Some(7));
+        assert_eq!(PrefixUnit::Char.length(OsStr::new("éclair")), Some(6));
+    }
+
+    #[test]
+    fn test_validate_path_component() {
+        for valid in ["a", "abc", "...", ".a", "a.", "a..b", "é"] {
+            assert_eq!(validate_path_component(valid), Ok(()), "{valid:?}");
+        }
+
+        for invalid in ["", ".", "..", "a/b", "/", "/etc", "a\0b", "../a"] {
+            assert_eq!(
+                validate_path_component(invalid),
+                Err(Error::InvalidPathComponent),
+                "{invalid:?}"
+            );
+        }
     }
 }
@@ -0,0 +1,249 @@
+use crate::{Error, Tree, iter, scheme::Scheme};
+use std::cmp::Ordering;
+use std::ffi::OsStr;
+use std::ops::Bound;
+
+/// A range of name strings whose bounds fall on prefix directory boundaries.
+///
+/// The start is inclusive and the end is exclusive, and a missing bound means the range is
+/// unbounded on that side.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Shard {
+    start: Option<String>,
+    end: Option<String>,
+}
+
+impl Shard {
+    #[must_use]
+    pub fn start(&self) -> Option<&str> {
+        self.start.as_deref()
+    }
+
+    #[must_use]
+    pub fn end(&self) -> Option<&str> {
+        self.end.as_deref()
+    }
+
+    /// Iterate over the entries in this shard.
+    #[must_use]
+    pub fn entries<'a, S: Scheme>(&self, tree: &'a Tree<S>) -> iter::Entries<'a, S> {
+        tree.entries().with_bounds(
+            self.start.clone().map_or(Bound::Unbounded, Bound::Included),
+            self.end.clone().map_or(Bound::Unbounded, Bound::Excluded),
+        )
+    }
+}
+
+impl<S: Scheme> Tree<S> {
+    /// Divide the names into at most `n` disjoint, ordered shards aligned to prefix directories.
+    ///
+    /// If the scheme has an alphabet, the boundaries are evenly spaced over all possible prefix
+    /// directories (using as many levels as needed to get `n` shards), and otherwise they are
+    /// chosen from the existing top-level prefix directories. Fewer than `n` shards are returned
+    /// if there are not enough prefix directories, and a single unbounded shard is returned if
+    /// there are no prefix parts.
+    ///
+    /// Iterating over each shard in order returns exactly the valid entries of `Tree::entries`.
+    pub fn shards(&self, n: usize) -> Result<Vec<Shard>, Error> {
+        let boundaries = match self.scheme.alphabet() {
+            Some(alphabet) => self.alphabet_boundaries(&alphabet.chars().collect::<Vec<_>>(), n),
+            None => self.directory_boundaries(n)?,
+        };
+
+        let starts = std::iter::once(None).chain(boundaries.iter().cloned().map(Some));
+        let ends = boundaries
+            .iter()
+            .cloned()
+            .map(Some)
+            .chain(std::iter::once(None));
+
+        Ok(starts
+            .zip(ends)
+            .map(|(start, end)| Shard { start, end })
+            .collect())
+    }
+
+    fn alphabet_boundaries(&self, alphabet: &[char], n: usize) -> Vec<String> {
+        let base = alphabet.len() as u128;
+        let target = n as u128;
+
+        let mut width = 0;
+        let mut total = 1u128;
+
+        for prefix_part_length in &self.prefix_part_lengths {
+            if total >= target || base < 2 {
+                break;
+            }
+
+            for _ in 0..*prefix_part_length {
+                total = total.saturating_mul(base);
+            }
+
+            width += prefix_part_length;
+        }
+
+        let count = total.min(target);
+
+        (1..count)
+            .map(|i| {
+                // This is `i * total / count` without overflow.
+                let mut index = (total / count) * i + (total % count) * i / count;
+                let mut digits = vec![alphabet[0]; width];
+
+                for digit in digits.iter_mut().rev() {
+                    *digit = alphabet[(index % base) as usize];
+                    index /= base;
+                }
+
+                digits.into_iter().collect()
+            })
+            .collect()
+    }
+
+    fn directory_boundaries(&self, n: usize) -> Result<Vec<String>, Error> {
+        if self.prefix_part_lengths.is_empty() {
+            Ok(vec![])
+        } else {
+            let mut directory_names = std::fs::read_dir(&self.base)?
+                .map(|entry| entry.map(|entry| entry.file_name()))
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .filter(|directory_name| !crate::is_reserved(directory_name))
+                .filter_map(|directory_name| directory_name.into_string().ok())
+                .collect::<Vec<_>>();
+
+            directory_names.sort_by(|a, b| self.cmp_name_strings(a, b));
+
+            let total = directory_names.len();
+            let count = total.min(n);
+
+            Ok((1..count)
+                .map(|i| directory_names[i * total / count].clone())
+                .collect())
+        }
+    }
+
+    fn cmp_name_strings(&self, a: &str, b: &str) -> Ordering {
+        let (a, b) = (OsStr::new(a), OsStr::new(b));
+
+        self.scheme
+            .cmp_prefix_part(a, b)
+            .unwrap_or_else(|_| a.cmp(b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Tree, scheme};
+
+    fn check_shards<S: scheme::Scheme>(
+        tree: &Tree<S>,
+        n: usize,
+    ) -> Result<usize, Box<dyn std::error::Error>>
+    where
+        S::Name: Eq + std::fmt::Debug,
+    {
+        let shards = tree.shards(n)?;
+        let expected = tree.entries().collect::<Result<Vec<_>, _>>()?;
+
+        let mut entries = vec![];
+
+        for shard in &shards {
+            entries.extend(shard.entries(tree).collect::<Result<Vec<_>, _>>()?);
+        }
+
+        assert_eq!(entries, expected);
+        assert!(shards.len() <= n.max(1));
+        assert_eq!(shards.first().and_then(super::Shard::start), None);
+        assert_eq!(shards.last().and_then(super::Shard::end), None);
+
+        Ok(shards.len())
+    }
+
+    #[test]
+    fn test_shards_hex() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::hex::Hex::<2>::default())
+            .with_prefix_part_lengths([1, 1])
+            .build()?;
+
+        for i in 0..300u16 {
+            drop(tree.create_file(i.wrapping_mul(40_503).to_be_bytes())?);
+        }
+
+        for n in [0, 1, 2, 3, 7, 16, 17, 100, 256] {
+            assert_eq!(check_shards(&tree, n)?, n.max(1));
+        }
+
+        // There are only 256 possible prefix directory paths.
+        assert_eq!(check_shards(&tree, 1000)?, 256);
+
+        let shards = tree.shards(4)?;
+        let boundaries = shards
+            .iter()
+            .filter_map(super::Shard::start)
+            .collect::<Vec<_>>();
+
+        assert_eq!(boundaries, vec!["4", "8", "c"]);
+
+        let shards = tree.shards(32)?;
+
+        assert_eq!(shards[1].start(), Some("08"));
+        assert_eq!(shards[1].end(), Some("10"));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "data-encoding")]
+    #[test]
+    fn test_shards_base32() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::encoding::Base32::<5>::new(scheme::Case::Upper))
+            .with_prefix_part_lengths([2])
+            .build()?;
+
+        for i in 0..200u8 {
+            drop(tree.create_file([i.wrapping_mul(97), i, 0, 0, 0])?);
+        }
+
+        for n in [1, 2, 5, 31, 32, 33, 1024] {
+            assert_eq!(check_shards(&tree, n)?, n);
+        }
+
+        assert_eq!(check_shards(&tree, 2000)?, 1024);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_shards_from_directories() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([1])
+            .build()?;
+
+        for name in ["apple", "banana", "cherry", "date", "elderberry", "fig"] {
+            drop(tree.create_file(name)?);
+        }
+
+        for n in 1..=6 {
+            assert_eq!(check_shards(&tree, n)?, n);
+        }
+
+        assert_eq!(check_shards(&tree, 10)?, 6);
+
+        let boundaries = tree
+            .shards(3)?
+            .iter()
+            .filter_map(super::Shard::start)
+            .map(std::string::ToString::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(boundaries, vec!["c", "e"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+use crate::{Error, Tree, scheme::Scheme};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The number of leaf directories reported in `Stats::fullest_leaf_directories`.
+const FULLEST_LEAF_DIRECTORY_COUNT: usize = 10;
+
+/// A summary of the size and shape of a tree.
+///
+/// Files are counted whether or not they are valid entries (see `Tree::check`), and reserved
+/// names are ignored.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Stats {
+    /// The number of files in leaf directories.
+    pub files: usize,
+    /// The total size of those files in bytes.
+    pub bytes: u64,
+    /// The number of directories for each prefix part.
+    pub directories_per_level: Vec<usize>,
+    /// The number of directories that contain files (the base directory if there are no prefix
+    /// parts).
+    pub leaf_directories: usize,
+    /// The smallest number of files in a leaf directory.
+    pub min_leaf_files: Option<usize>,
+    /// The largest number of files in a leaf directory.
+    pub max_leaf_files: Option<usize>,
+    /// The leaf directories with the most files, in descending order.
+    pub fullest_leaf_directories: Vec<(PathBuf, usize)>,
+    /// The number of files with each extension (or no extension).
+    pub extensions: BTreeMap<Option<String>, usize>,
+}
+
+impl Stats {
+    /// The mean number of files in a leaf directory.
+    #[must_use]
+    pub fn mean_leaf_files(&self) -> Option<f64> {
+        if self.leaf_directories == 0 {
+            None
+        } else {
+            #[allow(clippy::cast_precision_loss)]
+            Some(self.files as f64 / self.leaf_directories as f64)
+        }
+    }
+
+    fn add_leaf_directory(&mut self, path: &Path, files: usize) {
+        self.leaf_directories += 1;
+        self.min_leaf_files = Some(self.min_leaf_files.map_or(files, |min| min.min(files)));
+        self.max_leaf_files = Some(self.max_leaf_files.map_or(files, |max| max.max(files)));
+
+        let index = self
+            .fullest_leaf_directories
+            .partition_point(|(_, count)| *count >= files);
+
+        if index < FULLEST_LEAF_DIRECTORY_COUNT {
+            self.fullest_leaf_directories
+                .insert(index, (path.to_path_buf(), files));
+            self.fullest_leaf_directories
+                .truncate(FULLEST_LEAF_DIRECTORY_COUNT);
+        }
+    }
+}
+
+impl<S: Scheme> Tree<S> {
+    /// Walk the entire tree once and summarize its contents.
+    ///
+    /// This can be used to decide whether the prefix part lengths are still appropriate.
+    pub fn stats(&self) -> Result<Stats, Error> {
+        let mut stats = Stats {
+            directories_per_level: vec![0; self.prefix_part_lengths.len()],
+            ..Stats::default()
+        };
+
+        if self.base.is_dir() {
+            self.stats_directory(&self.base, 0, &mut stats)?;
+
+            Ok(stats)
+        } else {
+            Err(Error::ExpectedDirectory(self.base.clone()))
+        }
+    }
+
+    fn stats_directory(&self, path: &Path, level: usize, stats: &mut Stats) -> Result<(), Error> {
+        let is_leaf = level == self.prefix_part_lengths.len();
+        let mut files = 0;
+
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+
+            if !crate::is_reserved(&entry.file_name()) {
+                let entry_path = entry.path();
+
+                // We follow symbolic links here, for consistency with iteration, and skip broken
+                // ones.
+                let metadata = match std::fs::metadata(&entry_path) {
+                    Ok(metadata) => metadata,
+                    Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+                    Err(error) => return Err(error.into()),
+                };
+
+                if is_leaf && metadata.is_file() {
+                    files += 1;
+                    stats.bytes += metadata.len();
+
+                    let extension = entry_path
+                        .extension()
+                        .map(|extension| extension.to_string_lossy().into_owned());
+
+                    *stats.extensions.entry(extension).or_default() += 1;
+                } else if !is_leaf && metadata.is_dir() {
+                    stats.directories_per_level[level] += 1;
+
+                    self.stats_directory(&entry_path, level + 1, stats)?;
+                }
+            }
+        }
+
+        if is_leaf {
+            stats.files += files;
+            stats.add_leaf_directory(path, files);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Tree, scheme};
+    use std::io::Write;
+
+    #[test]
+    fn test_stats() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let base = temp_dir.path();
+        let tree = Tree::builder(base)
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([1, 1])
+            .with_extension("txt")
+            .build()?;
+
+        for name in ["abc", "abd", "abe", "acd", "bcd"] {
+            tree.create_file(name)?
+                .expect("Failed to create file")
+                .write_all(name.as_bytes())?;
+        }
+
+        std::fs::write(base.join("a/b/abf.md"), b"x")?;
+        std::fs::write(base.join("a/b/.prefix-file-tree-staging-1-1"), b"xyz")?;
+        std::fs::create_dir_all(base.join("c/d"))?;
+
+        let stats = tree.stats()?;
+
+        assert_eq!(stats.files, 6);
+        assert_eq!(stats.bytes, 16);
+        assert_eq!(stats.directories_per_level, vec![3, 4]);
+        assert_eq!(stats.leaf_directories, 4);
+        assert_eq!(stats.min_leaf_files, Some(0));
+        assert_eq!(stats.max_leaf_files, Some(4));
+        assert_eq!(stats.mean_leaf_files(), Some(1.5));
+        assert_eq!(stats.fullest_leaf_directories[0], (base.join("a/b"), 4));
+        assert_eq!(stats.fullest_leaf_directories[3], (base.join("c/d"), 0));
+        assert_eq!(
+            stats.extensions.into_iter().collect::<Vec<_>>(),
+            vec![(Some("md".to_string()), 1), (Some("txt".to_string()), 5)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stats_without_prefix_parts() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let tree = Tree::builder(temp_dir.path())
+            .with_scheme(scheme::Utf8)
+            .build()?;
+
+        let stats = tree.stats()?;
+
+        assert_eq!(stats.files, 0);
+        assert_eq!(stats.leaf_directories, 1);
+        assert_eq!(stats.mean_leaf_files(), Some(0.0));
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stats_symbolic_links() -> Result<(), Box<dyn std::error::Error>> {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = tempfile::tempdir()?;
+        let base = temp_dir.path();
+        let tree = Tree::builder(base)
+            .with_scheme(scheme::Utf8)
+            .with_prefix_part_lengths([1])
+            .build()?;
+
+        drop(tree.create_file("abc")?);
+
+        // Broken links are skipped.
+        symlink(base.join("missing"), base.join("a/abd"))?;
+
+        assert_eq!(tree.stats()?.files, 1);
+
+        // Other errors are not.
+        symlink(base.join("a/abe"), base.join("a/abe"))?;
+
+        assert!(matches!(tree.stats(), Err(crate::Error::Io(_))));
+
+        Ok(())
+    }
+}
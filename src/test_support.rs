@@ -0,0 +1,13 @@
+//! Helpers shared by the unit tests.
+
+use std::fs::File;
+use std::path::Path;
+
+/// Create an empty file, along with any missing parent directories.
+pub fn touch<P: AsRef<Path>>(path: P) -> std::io::Result<()> {
+    if let Some(parent) = path.as_ref().parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    File::create(path).map(drop)
+}